@@ -0,0 +1,103 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+#[allow(dead_code)]
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use std::str::FromStr;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use serde_json::{json, Value};
+use webthings_gateway_ipc_types::{
+    DevicePropertyChangedNotification, GenericMessage, Message, MessageHeader, MessageType,
+    MESSAGE_IDS,
+};
+
+/// A property change carrying an array of 10000 objects as its value
+fn large_property_message() -> String {
+    let mut value = common::instance(DevicePropertyChangedNotification::MESSAGE_ID);
+    let items: Vec<Value> = (0..10_000)
+        .map(|i| json!({ "index": i, "label": format!("item {}", i), "on": i % 2 == 0 }))
+        .collect();
+    value["data"]["property"]["value"] = Value::Array(items);
+    value.to_string()
+}
+
+/// Parsing as before the single parse of `from_str`: the input is parsed once to peek
+/// at its message type and a second time into the message
+fn parse_twice(s: &str) -> Message {
+    let msg: GenericMessage = serde_json::from_str(s).unwrap();
+    assert_eq!(
+        msg.message_type(),
+        DevicePropertyChangedNotification::MESSAGE_ID
+    );
+    serde_json::from_str::<DevicePropertyChangedNotification>(s)
+        .unwrap()
+        .into()
+}
+
+fn large_property(c: &mut Criterion) {
+    let json = large_property_message();
+    let mut group = c.benchmark_group("large property");
+
+    group.bench_function("from_str", |b| {
+        b.iter(|| Message::from_str(black_box(&json)).unwrap())
+    });
+    group.bench_function("parse twice", |b| b.iter(|| parse_twice(black_box(&json))));
+    #[cfg(all(feature = "simd-json", not(feature = "raw-value")))]
+    group.bench_function("simd-json", |b| {
+        b.iter_batched_ref(
+            || json.clone().into_bytes(),
+            |bytes| Message::from_slice(black_box(bytes)).unwrap(),
+            criterion::BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("MessageHeader", |b| {
+        b.iter(|| MessageHeader::from_str(black_box(&json)).unwrap())
+    });
+
+    group.finish();
+}
+
+/// One message of every type with all fields of its schema set
+fn message_mix(c: &mut Criterion) {
+    let messages: Vec<String> = MESSAGE_IDS
+        .iter()
+        .map(|id| common::instance(*id).to_string())
+        .collect();
+    let mut group = c.benchmark_group("message mix");
+
+    group.bench_function("from_str", |b| {
+        b.iter(|| {
+            for json in &messages {
+                Message::from_str(black_box(json)).unwrap();
+            }
+        })
+    });
+    // `from_slice` uses the buffers as scratch space, so every iteration gets fresh copies
+    #[cfg(all(feature = "simd-json", not(feature = "raw-value")))]
+    group.bench_function("simd-json", |b| {
+        b.iter_batched_ref(
+            || {
+                messages
+                    .iter()
+                    .map(|json| json.clone().into_bytes())
+                    .collect::<Vec<_>>()
+            },
+            |buffers| {
+                for bytes in buffers.iter_mut() {
+                    Message::from_slice(black_box(bytes)).unwrap();
+                }
+            },
+            criterion::BatchSize::SmallInput,
+        )
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, large_property, message_mix);
+criterion_main!(benches);
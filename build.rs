@@ -3,30 +3,776 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
  */
+use std::collections::hash_map::{DefaultHasher, HashMap};
+use std::env;
 use std::fs;
+use std::hash::{Hash, Hasher};
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 
 use os_pipe::{dup_stderr, dup_stdout};
+use sha2::{Digest, Sha256};
 
+mod defaults_generator;
+mod enums_generator;
 mod extras_generator;
+mod flatten_generator;
+
+/// The schema release checked out when cloning the schema repo
+const SCHEMA_GIT_TAG: &str = "v1.0.0";
 
 fn main() {
-    clone_schema_repo();
+    // Any rerun directive replaces rerunning on every change of the package,
+    // so the build script and generators are declared as well
+    for file in &[
+        "build.rs",
+        "defaults_generator.rs",
+        "enums_generator.rs",
+        "extras_generator.rs",
+        "flatten_generator.rs",
+    ] {
+        println!("cargo:rerun-if-changed={}", file);
+    }
+    for var in &[
+        "WEBTHINGS_SCHEMA_TARBALL",
+        "WEBTHINGS_SCHEMA_SHA256",
+        "WEBTHINGS_GENERATED_MODULE",
+    ] {
+        println!("cargo:rerun-if-env-changed={}", var);
+    }
+
+    let out_dir = PathBuf::from(env::var_os("OUT_DIR").expect("OUT_DIR is set"));
+    // The version of a tarball is unknown, so only a cloned schema can be linked
+    let schema_git_tag = match env::var("WEBTHINGS_SCHEMA_TARBALL") {
+        Ok(tarball) => {
+            extract_schema_tarball(&tarball, &out_dir);
+            None
+        }
+        Err(_) => {
+            clone_schema_repo();
+            Some(SCHEMA_GIT_TAG)
+        }
+    };
     let schema_path = Path::new("gateway-addon-ipc-schema/schema.json");
-    code_gen(
+    let types_file = out_dir.join("types.rs");
+    let extras_file = out_dir.join("extras.rs");
+    let hash_file = out_dir.join("schema.hash");
+
+    let schema_dir = schema_path.parent().expect("Schema has parent");
+    apply_schema_overrides(schema_dir);
+
+    // Only the tests of this crate parse the fixtures, other builds skip them
+    let fixtures = env::var_os("CARGO_FEATURE_FIXTURES")
+        .map(|_| fixture_schemas())
+        .unwrap_or_default();
+
+    let hash = input_hash(schema_dir, &fixtures);
+    let module_file = env::var_os("WEBTHINGS_GENERATED_MODULE").map(PathBuf::from);
+    let module_exists = match &module_file {
+        Some(file) => file.exists(),
+        None => true,
+    };
+    let cached = fs::read_to_string(&hash_file).ok() == Some(hash.clone())
+        && types_file.exists()
+        && extras_file.exists()
+        && out_dir.join("message_list.rs").exists()
+        && module_exists
+        && fixtures
+            .iter()
+            .all(|(name, _)| out_dir.join(fixture_file(name)).exists());
+
+    if cached {
+        return;
+    }
+
+    let types = schema_ref_docs(generate_types(schema_path), schema_path, schema_git_tag);
+    code_gen(types.clone(), &types_file);
+    let fingerprint = fingerprint_code(&types_file, &out_dir.join("types.api"));
+    let extras = extras_generator::generate(&schema_path, &types, "crate::types");
+    if let Some(module_file) = module_file {
+        code_gen(
+            self_contained_module(schema_path, &types) + &fingerprint,
+            &module_file,
+        );
+    }
+    code_gen(extras + &fingerprint, &extras_file);
+    fs::write(
+        out_dir.join("message_list.rs"),
+        extras_generator::message_list(schema_path, "message_test"),
+    )
+    .expect("Unable to write message list");
+    generate_fixtures(&fixtures, &out_dir);
+    fs::write(hash_file, hash).expect("Unable to write schema hash");
+}
+
+/// Generates the types of the schema repository at the parent of `schema_path`
+fn generate_types(schema_path: &Path) -> String {
+    let schema_dir = schema_path.parent().expect("Schema has parent");
+    let files = schema_files(schema_dir);
+    let discriminator = extras_generator::discriminator(schema_path);
+    let types = message_type_fields(
         jsonschema_code_generator::generate(&schema_path),
-        "src/types.rs",
+        &discriminator,
+    );
+    // Sharing runs first, the enums and defaults are named after the struct they are generated for
+    let types = defaults_generator::apply(
+        enums_generator::apply(share_identical_structs(types), &files),
+        &files,
+    );
+    schemars_derives(arbitrary_derives(camel_case_fallback(arbitrary_precision(
+        raw_values(flatten_generator::apply(types, &files, &discriminator)),
+    ))))
+}
+
+/// The name and schema of each schema repository in `tests/fixtures`,
+/// e.g. `top_level` for `tests/fixtures/top-level/schema.json`
+fn fixture_schemas() -> Vec<(String, PathBuf)> {
+    let fixtures = Path::new("tests/fixtures");
+    if !fixtures.exists() {
+        return Vec::new();
+    }
+    println!("cargo:rerun-if-changed={}", fixtures.display());
+
+    let mut schemas = Vec::new();
+    let entries = fs::read_dir(fixtures).expect("Read fixtures dir");
+    for entry in entries {
+        let dir = entry.expect("Read fixtures dir entry").path();
+        let schema_path = dir.join("schema.json");
+        if !schema_path.exists() {
+            continue;
+        }
+        let name = dir
+            .file_name()
+            .and_then(|name| name.to_str())
+            .expect("Fixture name is UTF-8")
+            .replace('-', "_");
+        schemas.push((name, schema_path));
+    }
+
+    schemas.sort();
+    schemas
+}
+
+fn fixture_file(name: &str) -> String {
+    format!("fixture_{}.rs", name)
+}
+
+/// Generates a self-contained module for each fixture schema repository,
+/// e.g. `tests/fixtures/top-level` to `fixture_top_level.rs` in the `OUT_DIR`.
+/// The tests include them to parse messages of shapes the schema repository does not have.
+fn generate_fixtures(fixtures: &[(String, PathBuf)], out_dir: &Path) {
+    for (name, schema_path) in fixtures {
+        let types = generate_types(schema_path);
+        code_gen(
+            self_contained_module(schema_path, &types),
+            &out_dir.join(fixture_file(name)),
+        );
+    }
+}
+
+/// The types and extras for `WEBTHINGS_GENERATED_MODULE`, which can be included in any module
+/// of another crate as they only refer to each other relatively.
+fn self_contained_module(schema_path: &Path, types: &str) -> String {
+    let module = format!(
+        "pub mod types {{ {types} }} \
+         pub mod extras {{ {extras} }} \
+         pub use self::extras::*; \
+         pub use self::types::*;",
+        types = types.replace("crate :: extras ::", "super :: extras ::"),
+        extras = extras_generator::generate(schema_path, types, "super::types"),
     );
-    code_gen(extras_generator::generate(&schema_path), "src/extras.rs");
+    resolve_feature_gates(&module)
 }
 
-fn code_gen(code: String, file: &str) {
+/// Replaces each `feature = "name"` predicate by `all()` if the feature of this crate is enabled
+/// and by `any()` otherwise, so the gates of vendored code do not depend on the features of
+/// the crate it is included in.
+fn resolve_feature_gates(code: &str) -> String {
+    let mut output = "".to_owned();
+    let mut rest = code;
+
+    while let Some(start) = rest.find("feature = \"") {
+        let name_start = start + "feature = \"".len();
+        let name_end = name_start + rest[name_start..].find('"').expect("Feature name is closed");
+        let name = &rest[name_start..name_end];
+        let enabled = env::var_os(format!(
+            "CARGO_FEATURE_{}",
+            name.to_uppercase().replace('-', "_")
+        ))
+        .is_some();
+
+        output += &rest[..start];
+        output += if enabled { "all()" } else { "any()" };
+        rest = &rest[name_end + 1..];
+    }
+
+    output + rest
+}
+
+/// Writes the generated types without their docs to `api_file` and declares its hash,
+/// so two versions can be compared through the hash and diffed through the file.
+fn fingerprint_code(types_file: &Path, api_file: &Path) -> String {
+    let api: String = fs::read_to_string(types_file)
+        .expect("Unable to read types")
+        .lines()
+        .filter(|line| !line.trim_start().starts_with("#[doc"))
+        .map(|line| format!("{}\n", line))
+        .collect();
+    fs::write(api_file, api).expect("Unable to write type signatures");
+
+    format!(
+        "/// SHA-256 of the generated types without their docs, changes with any field or type
+         ///
+         /// The types depend on the schema overrides and features, the hashed signatures are
+         /// written to `types.api` in the `OUT_DIR` of the build.
+         pub const GENERATED_TYPE_FINGERPRINT: &str = {:?};",
+        sha256(api_file)
+    )
+}
+
+fn code_gen(code: String, file: &Path) {
     let rust_code_types = format(code);
     fs::write(file, rust_code_types).expect("Unable to write file");
 }
 
+/// Hashes everything the generated code depends on:
+/// the schema and fixture files, the generators and their dependencies, rustfmt and the enabled features.
+fn input_hash(schema_dir: &Path, fixtures: &[(String, PathBuf)]) -> String {
+    let mut hasher = DefaultHasher::new();
+
+    let fixture_files = fixtures.iter().flat_map(|(_, schema_path)| {
+        schema_files(schema_path.parent().expect("Fixture schema has parent"))
+    });
+    for file in schema_files(schema_dir).into_iter().chain(fixture_files) {
+        file.hash(&mut hasher);
+        fs::read(&file)
+            .unwrap_or_else(|e| panic!("Read schema file {}: {}", file.display(), e))
+            .hash(&mut hasher);
+    }
+
+    include_str!("build.rs").hash(&mut hasher);
+    include_str!("defaults_generator.rs").hash(&mut hasher);
+    include_str!("enums_generator.rs").hash(&mut hasher);
+    include_str!("extras_generator.rs").hash(&mut hasher);
+    include_str!("flatten_generator.rs").hash(&mut hasher);
+    // The dependencies of the generators are compiled into the build script
+    fs::read(env::current_exe().expect("Build script path is known"))
+        .expect("Read build script")
+        .hash(&mut hasher);
+    Command::new("rustfmt")
+        .arg("--version")
+        .output()
+        .map(|output| output.stdout)
+        .ok()
+        .hash(&mut hasher);
+
+    let mut features: Vec<String> = env::vars()
+        .map(|(key, _)| key)
+        .filter(|key| key.starts_with("CARGO_FEATURE_"))
+        .collect();
+    features.sort();
+    features.hash(&mut hasher);
+    env::var_os("WEBTHINGS_GENERATED_MODULE").hash(&mut hasher);
+
+    format!("{:016x}", hasher.finish())
+}
+
+/// Adds the `$ref` of its schema file to the doc of each message struct,
+/// with a link to the file in the schema repo if the release is known.
+fn schema_ref_docs(code: String, schema_path: &Path, git_tag: Option<&str>) -> String {
+    let schema_dir = schema_path.parent().expect("Schema has parent");
+    let mut code = code;
+
+    for file in extras_generator::message_files(schema_path) {
+        let doc = format!(
+            "# [doc = \"Generated from {}\"]",
+            schema_dir.join(&file).display()
+        );
+        let link = match git_tag {
+            Some(tag) => format!(
+                ", see <https://github.com/WebThingsIO/gateway-addon-ipc-schema/blob/{}/{}>",
+                tag, file
+            ),
+            None => "".to_owned(),
+        };
+        let docs = format!(
+            "{} # [doc = \"\"] # [doc = \"Schema `{}`{}\"]",
+            doc, file, link
+        );
+        code = code.replacen(&doc, &docs, 1);
+    }
+
+    code
+}
+
+/// Merges the overrides from `WEBTHINGS_SCHEMA_OVERRIDES` or `schema-overrides.json`
+/// into the schema files before anything is generated.
+/// The overrides map schema file paths relative to the schema dir to JSON merge patches.
+///
+/// A missing file would rerun the build script on every build, so only an existing one is watched.
+fn apply_schema_overrides(schema_dir: &Path) {
+    println!("cargo:rerun-if-env-changed=WEBTHINGS_SCHEMA_OVERRIDES");
+    let overrides_file = match env::var_os("WEBTHINGS_SCHEMA_OVERRIDES") {
+        Some(file) => PathBuf::from(file),
+        None if Path::new("schema-overrides.json").exists() => {
+            PathBuf::from("schema-overrides.json")
+        }
+        None => return,
+    };
+    println!("cargo:rerun-if-changed={}", overrides_file.display());
+
+    let overrides: serde_json::Map<String, serde_json::Value> = serde_json::from_str(
+        &fs::read_to_string(&overrides_file)
+            .unwrap_or_else(|e| panic!("Read overrides {}: {}", overrides_file.display(), e)),
+    )
+    .unwrap_or_else(|e| panic!("Parse overrides {}: {}", overrides_file.display(), e));
+
+    for (file, patch) in overrides {
+        let path = schema_dir.join(&file);
+        let mut schema: serde_json::Value = serde_json::from_str(
+            &fs::read_to_string(&path)
+                .unwrap_or_else(|e| panic!("Read overridden schema {}: {}", path.display(), e)),
+        )
+        .unwrap_or_else(|e| panic!("Parse overridden schema {}: {}", path.display(), e));
+        merge_patch(&mut schema, patch);
+        fs::write(&path, schema.to_string())
+            .unwrap_or_else(|e| panic!("Write overridden schema {}: {}", path.display(), e));
+    }
+}
+
+/// Applies a JSON merge patch (RFC 7386), `null` removes a key
+fn merge_patch(target: &mut serde_json::Value, patch: serde_json::Value) {
+    match (target, patch) {
+        (serde_json::Value::Object(target), serde_json::Value::Object(patch)) => {
+            for (key, value) in patch {
+                if value.is_null() {
+                    target.remove(&key);
+                } else {
+                    merge_patch(target.entry(key).or_insert(serde_json::Value::Null), value);
+                }
+            }
+        }
+        (target, patch) => *target = patch,
+    }
+}
+
+fn schema_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+
+    let entries =
+        fs::read_dir(dir).unwrap_or_else(|e| panic!("Read schema dir {}: {}", dir.display(), e));
+
+    for entry in entries {
+        let path = entry.expect("Read schema dir entry").path();
+        if path.is_dir() {
+            if path.file_name() != Some(".git".as_ref()) {
+                files.append(&mut schema_files(&path));
+            }
+        } else if path.extension() == Some("json".as_ref()) {
+            files.push(path);
+        }
+    }
+
+    files.sort();
+    files
+}
+
+/// Names the discriminator field of the message structs `message_type`, which the extras rely on.
+/// The wire name is kept by a serde rename.
+fn message_type_fields(code: String, discriminator: &str) -> String {
+    const DOC: &str = "# [doc = \"Generated from ";
+
+    let field = extras_generator::field_name(discriminator);
+    let generated = match field == discriminator {
+        true => format!("pub {} : i64", field),
+        false => format!(
+            "# [serde (rename = {:?})] pub {} : i64",
+            discriminator, field
+        ),
+    };
+    let renamed = format!(
+        "# [serde (rename = {:?})] pub message_type : i64",
+        discriminator
+    );
+    if generated == renamed {
+        return code;
+    }
+
+    let mut output = "".to_owned();
+    let mut rest = code.as_str();
+
+    // Only the structs of the message schema files, nested structs keep a field of the same name
+    while let Some(start) = rest.find(DOC) {
+        let doc_end = start + rest[start..].find("\"]").expect("Doc is closed") + 2;
+        let body_end = match rest[doc_end..].find('}') {
+            Some(offset) => doc_end + offset,
+            None => break,
+        };
+
+        output += &rest[..doc_end];
+        if rest[..doc_end - 2].ends_with(".json") {
+            output += &rest[doc_end..body_end].replacen(&generated, &renamed, 1);
+        } else {
+            output += &rest[doc_end..body_end];
+        }
+        rest = &rest[body_end..];
+    }
+
+    output + rest
+}
+
+/// Replaces nested structs of the message data which are identical to an earlier one
+/// with an alias of the earlier one, e.g. `type Device1 = Device`.
+/// The message and message data structs are kept, as their conversions are type specific.
+///
+/// The enum values and defaults of the fields are compared as well,
+/// as the enums and defaults generated for them later are only generated for the shared struct.
+fn share_identical_structs(code: String) -> String {
+    const DOC: &str = "# [doc = \"Generated from ";
+
+    let mut output = "".to_owned();
+    let mut rest = code.as_str();
+    let mut structs: HashMap<String, String> = HashMap::new();
+    let mut schemas: HashMap<String, serde_json::Value> = HashMap::new();
+
+    while let Some(start) = rest.find(DOC) {
+        let doc_end = start + rest[start..].find("\"]").expect("Doc is closed") + 2;
+        let path = &rest[start + DOC.len()..doc_end - 2];
+        let struct_start = match rest[doc_end..].find("pub ") {
+            Some(offset) if rest[doc_end + offset..].starts_with("pub struct ") => doc_end + offset,
+            _ => {
+                output += &rest[..doc_end];
+                rest = &rest[doc_end..];
+                continue;
+            }
+        };
+        let name_start = struct_start + "pub struct ".len();
+        let body_start = name_start + rest[name_start..].find('{').expect("Struct has body");
+        let body_end =
+            body_start + rest[body_start..].find('}').expect("Struct body is closed") + 1;
+        let name = rest[name_start..body_start].trim();
+        let definition = format!(
+            "{}{}{}",
+            &rest[doc_end..struct_start],
+            &rest[body_start..body_end],
+            property_constraints(path, &mut schemas)
+        );
+
+        output += &rest[..start];
+        match structs.get(&definition) {
+            Some(shared) if path.contains("/properties/data/") => {
+                if shared != name {
+                    output += &format!("{}pub type {} = {} ;", &rest[start..doc_end], name, shared);
+                }
+            }
+            _ => {
+                if path.contains("/properties/data/") {
+                    structs.entry(definition).or_insert_with(|| name.to_owned());
+                }
+                output += &rest[start..body_end];
+            }
+        }
+        rest = &rest[body_end..];
+    }
+
+    output + rest
+}
+
+/// The enum values and defaults of the properties of the schema object at `path`,
+/// e.g. `gateway-addon-ipc-schema/messages/x.json/properties/data/properties/device`
+fn property_constraints(path: &str, schemas: &mut HashMap<String, serde_json::Value>) -> String {
+    let (file, pointer) = match path.find(".json") {
+        Some(end) => path.split_at(end + ".json".len()),
+        None => return "".to_owned(),
+    };
+    let schema = schemas.entry(file.to_owned()).or_insert_with(|| {
+        serde_json::from_str(
+            &fs::read_to_string(file)
+                .unwrap_or_else(|e| panic!("Read schema file {}: {}", file, e)),
+        )
+        .unwrap_or_else(|e| panic!("Parse JSON schema {}: {}", file, e))
+    });
+
+    match schema
+        .pointer(pointer)
+        .and_then(|object| object.get("properties"))
+        .and_then(serde_json::Value::as_object)
+    {
+        Some(properties) => properties
+            .iter()
+            .map(|(name, property)| {
+                format!(
+                    "{}: {:?} {:?};",
+                    name,
+                    property.get("enum"),
+                    property.get("default")
+                )
+            })
+            .collect(),
+        None => "".to_owned(),
+    }
+}
+
+/// Maps untyped schema fields to `Box<RawValue>` if the `raw-value` feature is enabled.
+/// Fields of type `Value` for other schemas, e.g. a `oneOf`, keep their type.
+/// `RawValue` does not implement `PartialEq`, so the derive is dropped from the structs
+/// which contain one, directly or through another struct.
+fn raw_values(code: String) -> String {
+    const DOC: &str = "# [doc = \"Generated from ";
+    const RAW_VALUE: &str = "Box < serde_json :: value :: RawValue >";
+
+    if env::var_os("CARGO_FEATURE_RAW_VALUE").is_none() {
+        return code;
+    }
+
+    let mut output = "".to_owned();
+    let mut rest = code.as_str();
+    let mut schemas: HashMap<String, serde_json::Value> = HashMap::new();
+
+    while let Some(start) = rest.find(DOC) {
+        let doc_end = start + rest[start..].find("\"]").expect("Doc is closed") + 2;
+        let path = &rest[start + DOC.len()..doc_end - 2];
+        let body_start = match rest[doc_end..].find("pub ") {
+            Some(offset) if rest[doc_end + offset..].starts_with("pub struct ") => {
+                doc_end + rest[doc_end..].find('{').expect("Struct has body")
+            }
+            _ => {
+                output += &rest[..doc_end];
+                rest = &rest[doc_end..];
+                continue;
+            }
+        };
+        let body_end = body_start + rest[body_start..].find('}').expect("Struct body is closed");
+
+        output += &rest[..body_start];
+        let mut body = rest[body_start..body_end].to_owned();
+        for property in untyped_properties(path, &mut schemas) {
+            let field = format!("pub {} : ", extras_generator::field_name(&property));
+            if let Some(offset) = body.find(&field) {
+                let type_start = offset + field.len();
+                let type_end = type_start + extras_generator::type_len(&body[type_start..]);
+                let type_: Vec<&str> = body[type_start..type_end]
+                    .split(' ')
+                    .map(|token| match token {
+                        "Value" => RAW_VALUE,
+                        token => token,
+                    })
+                    .collect();
+                body.replace_range(type_start..type_end, &type_.join(" "));
+            }
+        }
+        output += &body;
+        rest = &rest[body_end..];
+    }
+    output += rest;
+
+    // Only the import is left if every `Value` field is untyped
+    if output.matches(" Value ").count() == 1 {
+        output = output.replacen("use serde_json :: Value ;", "", 1);
+    }
+    without_partial_eq(&output, "RawValue")
+}
+
+/// The properties of the schema object at `path` which accept any value, or are objects or arrays
+/// of such values, e.g. a property value
+fn untyped_properties(path: &str, schemas: &mut HashMap<String, serde_json::Value>) -> Vec<String> {
+    fn untyped(schema: &serde_json::Value) -> bool {
+        match schema.get("type").and_then(serde_json::Value::as_str) {
+            None => ["type", "$ref", "oneOf", "anyOf", "allOf", "enum", "const"]
+                .iter()
+                .all(|key| schema.get(key).is_none()),
+            Some("object") => schema.get("properties").is_none(),
+            Some("array") => match schema.get("items") {
+                Some(items) => untyped(items),
+                None => true,
+            },
+            Some(_) => false,
+        }
+    }
+
+    let (file, pointer) = match path.find(".json") {
+        Some(end) => path.split_at(end + ".json".len()),
+        None => return Vec::new(),
+    };
+    let schema = schemas.entry(file.to_owned()).or_insert_with(|| {
+        serde_json::from_str(
+            &fs::read_to_string(file)
+                .unwrap_or_else(|e| panic!("Read schema file {}: {}", file, e)),
+        )
+        .unwrap_or_else(|e| panic!("Parse JSON schema {}: {}", file, e))
+    });
+
+    // The data structs of top-level messages are split off their message, see `flatten_generator`
+    let object = schema.pointer(pointer).or_else(|| {
+        pointer
+            .strip_suffix("/properties/data")
+            .and_then(|pointer| schema.pointer(pointer))
+    });
+    match object
+        .and_then(|object| object.get("properties"))
+        .and_then(serde_json::Value::as_object)
+    {
+        Some(properties) => properties
+            .iter()
+            .filter(|(_, property)| untyped(property))
+            .map(|(name, _)| name.clone())
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+/// Drops the `PartialEq` derive of the structs and aliases which contain the `token`,
+/// directly or through another struct or alias
+fn without_partial_eq(code: &str, token: &str) -> String {
+    let identifiers = |code: &str| -> Vec<String> {
+        code.split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            .filter(|identifier| !identifier.is_empty())
+            .map(str::to_owned)
+            .collect()
+    };
+
+    // The name of each struct and alias with the identifiers of its fields or target
+    let mut items: Vec<(String, Vec<String>)> = Vec::new();
+    let mut rest = code;
+    while let Some(start) = rest.find("pub ") {
+        let item = &rest[start + "pub ".len()..];
+        let (name, end) = if let Some(item) = item.strip_prefix("struct ") {
+            let body_end = item.find('}').unwrap_or(item.len());
+            let name_end = item.find('{').unwrap_or(body_end);
+            (item[..name_end].trim(), &item[name_end..body_end])
+        } else if let Some(item) = item.strip_prefix("type ") {
+            let end = item.find(';').unwrap_or(item.len());
+            let name_end = item.find('=').unwrap_or(end);
+            (item[..name_end].trim(), &item[name_end..end])
+        } else {
+            rest = item;
+            continue;
+        };
+        items.push((name.to_owned(), identifiers(end)));
+        rest = item;
+    }
+
+    let mut without: Vec<String> = vec![token.to_owned()];
+    loop {
+        let found: Vec<String> = items
+            .iter()
+            .filter(|(name, identifiers)| {
+                !without.contains(name) && identifiers.iter().any(|i| without.contains(i))
+            })
+            .map(|(name, _)| name.clone())
+            .collect();
+        if found.is_empty() {
+            break;
+        }
+        without.extend(found);
+    }
+
+    let mut output = "".to_owned();
+    let mut rest = code;
+    while let Some(start) = rest.find("pub struct ") {
+        let name_start = start + "pub struct ".len();
+        let name_end = name_start + rest[name_start..].find('{').expect("Struct has body");
+        let derive_start = rest[..start].rfind("# [derive (").unwrap_or(start);
+
+        output += &rest[..derive_start];
+        if without.iter().any(|name| name == rest[name_start..name_end].trim()) {
+            output += &rest[derive_start..start].replacen("PartialEq , ", "", 1);
+        } else {
+            output += &rest[derive_start..start];
+        }
+        output += &rest[start..name_end];
+        rest = &rest[name_end..];
+    }
+
+    output + rest
+}
+
+/// Maps number fields to `serde_json::Number` if the `arbitrary-precision` feature is enabled.
+fn arbitrary_precision(code: String) -> String {
+    if env::var_os("CARGO_FEATURE_ARBITRARY_PRECISION").is_none() {
+        return code;
+    }
+
+    code.replace(" f64 ", " serde_json :: Number ")
+}
+
+/// Adds `rename_all = "camelCase"` to every struct if the `camel-case-fallback` feature is enabled.
+/// Explicit field renames of the generator take precedence.
+fn camel_case_fallback(code: String) -> String {
+    if env::var_os("CARGO_FEATURE_CAMEL_CASE_FALLBACK").is_none() {
+        return code;
+    }
+
+    code.replace(
+        "pub struct ",
+        "# [serde (rename_all = \"camelCase\")] pub struct ",
+    )
+}
+
+/// Derives `Arbitrary` for the data structs if the `arbitrary` feature is enabled.
+/// Message structs are skipped, their message type is fixed in a manual impl of the extras.
+/// JSON values and numbers are generated by `arbitrary_json` to keep them valid JSON.
+fn arbitrary_derives(code: String) -> String {
+    if env::var_os("CARGO_FEATURE_ARBITRARY").is_none() {
+        return code;
+    }
+
+    let mut output = "".to_owned();
+    let mut rest = code.as_str();
+
+    while let Some(start) = rest.find("pub struct ") {
+        let body_start = start + rest[start..].find('{').expect("Struct has body");
+        let body_end = body_start + rest[body_start..].find('}').expect("Struct body is closed");
+        let body = &rest[body_start..body_end];
+
+        output += &rest[..start];
+        if body.contains("pub message_type : ") {
+            output += &rest[start..body_end];
+        } else {
+            output += "# [derive (arbitrary :: Arbitrary)] ";
+            output += &rest[start..body_start];
+            output += &arbitrary_fields(body);
+        }
+        rest = &rest[body_end..];
+    }
+
+    output + rest
+}
+
+/// Derives `JsonSchema` for the generated structs if the `schemars` feature is enabled.
+///
+/// The derive follows the serde attributes, so the schemas describe the wire format.
+fn schemars_derives(code: String) -> String {
+    if env::var_os("CARGO_FEATURE_SCHEMARS").is_none() {
+        return code;
+    }
+
+    code.replace("pub struct ", "# [derive (schemars :: JsonSchema)] pub struct ")
+}
+
+fn arbitrary_fields(body: &str) -> String {
+    let mut output = "".to_owned();
+    let mut rest = body;
+
+    while let Some(start) = rest.find("pub ") {
+        let type_start = start + rest[start..].find(" : ").expect("Field has type") + 3;
+        let type_end = type_start + extras_generator::type_len(&rest[type_start..]);
+        let json = rest[type_start..type_end]
+            .split(' ')
+            .any(|token| ["Value", "RawValue", "Number", "f64"].contains(&token));
+
+        output += &rest[..start];
+        if json {
+            output += "# [arbitrary (with = crate :: extras :: arbitrary_json)] ";
+        }
+        output += &rest[start..type_end];
+        rest = &rest[type_end..];
+    }
+
+    output + rest
+}
+
 fn clone_schema_repo() {
     Command::new("rm")
         .arg("-rf")
@@ -48,13 +794,74 @@ fn clone_schema_repo() {
         .arg("-C")
         .arg("gateway-addon-ipc-schema")
         .arg("checkout")
-        .arg("v1.0.0")
+        .arg(SCHEMA_GIT_TAG)
         .stdout(dup_stdout().expect("Could not redirect stdout"))
         .stderr(dup_stderr().expect("Could not redirect stderr"))
         .output()
         .expect("Could not checkout correct schema version");
 }
 
+/// Uses a pinned schema release instead of cloning the schema repo.
+/// The tarball is either a local file or an URL, which is downloaded into `OUT_DIR` once.
+/// Its SHA-256 checksum has to match `WEBTHINGS_SCHEMA_SHA256`.
+fn extract_schema_tarball(tarball: &str, out_dir: &Path) {
+    let expected = env::var("WEBTHINGS_SCHEMA_SHA256")
+        .expect("WEBTHINGS_SCHEMA_SHA256 is required for WEBTHINGS_SCHEMA_TARBALL")
+        .to_lowercase();
+
+    let archive = if Path::new(tarball).exists() {
+        println!("cargo:rerun-if-changed={}", tarball);
+        PathBuf::from(tarball)
+    } else {
+        let cached = out_dir.join("gateway-addon-ipc-schema.tar.gz");
+        if !cached.exists() || sha256(&cached) != expected {
+            let status = Command::new("curl")
+                .arg("-sSfL")
+                .arg("-o")
+                .arg(&cached)
+                .arg(tarball)
+                .stdout(dup_stdout().expect("Could not redirect stdout"))
+                .stderr(dup_stderr().expect("Could not redirect stderr"))
+                .status()
+                .expect("Could not download schema tarball");
+            assert!(status.success(), "Could not download schema tarball");
+        }
+        cached
+    };
+
+    let actual = sha256(&archive);
+    assert!(
+        actual == expected,
+        "Schema tarball checksum mismatch: expected {} but got {}",
+        expected,
+        actual
+    );
+
+    let _ = fs::remove_dir_all("gateway-addon-ipc-schema");
+    fs::create_dir("gateway-addon-ipc-schema").expect("Could not create schema dir");
+    let status = Command::new("tar")
+        .arg("-xzf")
+        .arg(&archive)
+        .arg("-C")
+        .arg("gateway-addon-ipc-schema")
+        .arg("--strip-components=1")
+        .stdout(dup_stdout().expect("Could not redirect stdout"))
+        .stderr(dup_stderr().expect("Could not redirect stderr"))
+        .status()
+        .expect("Could not extract schema tarball");
+    assert!(status.success(), "Could not extract schema tarball");
+}
+
+fn sha256(file: &Path) -> String {
+    let content =
+        fs::read(file).unwrap_or_else(|e| panic!("Read {} for checksum: {}", file.display(), e));
+
+    Sha256::digest(&content)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
 fn format(text: impl std::fmt::Display) -> String {
     let mut rustfmt = Command::new("rustfmt")
         .stdin(Stdio::piped())
@@ -0,0 +1,221 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+use std::{fs::File, path::PathBuf};
+
+use convert_case::{Case, Casing};
+use serde_json::Value;
+
+use crate::extras_generator::{field_name, type_len};
+
+/// A property which has a default value in the schema
+struct DefaultProperty {
+    object_src: String,
+    property: String,
+    default: Value,
+}
+
+/// Adds `#[serde(default)]` to non-optional fields with a schema default.
+/// Optional fields are left alone, an omitted field stays `None`.
+pub fn apply(code: String, schema_files: &[PathBuf]) -> String {
+    let mut code = code;
+    let mut functions = "".to_owned();
+
+    for file in schema_files {
+        let schema: Value = serde_json::from_reader(
+            File::open(file)
+                .unwrap_or_else(|e| panic!("Open schema file {}: {}", file.display(), e)),
+        )
+        .unwrap_or_else(|e| panic!("Parse JSON schema {}: {}", file.display(), e));
+
+        let mut properties = Vec::new();
+        find_default_properties(&schema, file.display().to_string(), &mut properties);
+
+        for property in properties {
+            if let Some(function) = add_default(&mut code, &property) {
+                functions += &function;
+            }
+        }
+    }
+
+    code + &functions
+}
+
+fn find_default_properties(schema: &Value, src: String, found: &mut Vec<DefaultProperty>) {
+    if let Some(items) = schema.get("items") {
+        find_default_properties(items, format!("{}/items", src), found);
+    }
+
+    let properties = match schema.get("properties").and_then(Value::as_object) {
+        Some(properties) => properties,
+        None => return,
+    };
+
+    for (name, property) in properties {
+        if let Some(default) = property.get("default") {
+            found.push(DefaultProperty {
+                object_src: src.clone(),
+                property: name.clone(),
+                default: default.clone(),
+            });
+        }
+        find_default_properties(property, format!("{}/properties/{}", src, name), found);
+    }
+}
+
+fn add_default(code: &mut String, property: &DefaultProperty) -> Option<String> {
+    let doc = format!("\"Generated from {}\"]", property.object_src);
+    let doc_start = code.find(&doc)?;
+    let struct_start = doc_start + code[doc_start..].find("pub struct ")?;
+    // A struct shared with an earlier one is an alias, which gets what the shared struct gets
+    if code[doc_start..struct_start].contains("pub type ") {
+        return None;
+    }
+    let name_start = struct_start + "pub struct ".len();
+    let body_start = name_start + code[name_start..].find('{')?;
+    let body_end = body_start + code[body_start..].find('}')?;
+    let struct_name = code[name_start..body_start].trim().to_owned();
+
+    let field = format!("pub {} : ", field_name(&property.property));
+    let field_start = body_start + code[body_start..body_end].find(&field)?;
+    let type_start = field_start + field.len();
+    let type_end = type_start + type_len(&code[type_start..body_end]);
+    let field_type = code[type_start..type_end].trim().to_owned();
+    if field_type.starts_with("Option <") {
+        return None;
+    }
+
+    // The default is parsed when a message omits the field, so a mismatch fails the build instead
+    if let Err(e) = check_default(code, &field_type, &property.default) {
+        panic!(
+            "Default {} of {}/properties/{} does not match the field type {}: {}",
+            property.default, property.object_src, property.property, field_type, e
+        );
+    }
+
+    let function = format!(
+        "default_{}_{}",
+        struct_name.to_case(Case::Snake),
+        field_name(&property.property).trim_end_matches('_')
+    );
+    code.insert_str(
+        field_start,
+        &format!("# [serde (default = {:?})] ", function),
+    );
+
+    Some(format!(
+        "
+        fn {function}() -> {field_type} {{
+            serde_json::from_str({json:?}).expect(\"Schema default matches the field type\")
+        }}
+        ",
+        function = function,
+        field_type = field_type,
+        json = property.default.to_string(),
+    ))
+}
+
+/// Checks that `value` deserializes into the generated type `type_`, e.g. `Vec < String >`
+fn check_default(code: &str, type_: &str, value: &Value) -> Result<(), String> {
+    let type_ = type_.trim();
+    let inner = |prefix: &str| {
+        type_
+            .strip_prefix(prefix)
+            .and_then(|inner| inner.strip_suffix('>'))
+            .map(str::trim)
+    };
+    let mismatch = || Err(format!("{} is not a {}", value, type_));
+
+    if let Some(inner) = inner("Option <") {
+        return match value {
+            Value::Null => Ok(()),
+            value => check_default(code, inner, value),
+        };
+    }
+    if let Some(inner) = inner("Box <") {
+        return check_default(code, inner, value);
+    }
+    if let Some(inner) = inner("Vec <") {
+        return match value {
+            Value::Array(items) => items
+                .iter()
+                .try_for_each(|item| check_default(code, inner, item)),
+            _ => mismatch(),
+        };
+    }
+    if let Some(inner) = inner("BTreeMap < String ,") {
+        return match value {
+            Value::Object(map) => map
+                .values()
+                .try_for_each(|item| check_default(code, inner, item)),
+            _ => mismatch(),
+        };
+    }
+
+    let matches = match type_ {
+        "Value" | "serde_json :: Value" => true,
+        "String" => value.is_string(),
+        "bool" => value.is_boolean(),
+        "f64" => value.is_number(),
+        "i64" => value.is_i64(),
+        "u64" => value.is_u64(),
+        name => return check_named_default(code, name, value),
+    };
+    match matches {
+        true => Ok(()),
+        false => mismatch(),
+    }
+}
+
+/// Checks a default of a generated struct, alias or enum
+fn check_named_default(code: &str, name: &str, value: &Value) -> Result<(), String> {
+    // Generated enums keep unknown values in their `Other` variant
+    if code.contains(&format!("pub enum {} ", name)) {
+        return match value {
+            Value::String(_) => Ok(()),
+            _ => Err(format!("{} is not a string for {}", value, name)),
+        };
+    }
+    if let Some(start) = code.find(&format!("pub type {} = ", name)) {
+        let target_start = start + format!("pub type {} = ", name).len();
+        let target_end = target_start + code[target_start..].find(';').unwrap_or(0);
+        return check_default(code, &code[target_start..target_end], value);
+    }
+
+    let start = code
+        .find(&format!("pub struct {} ", name))
+        .ok_or_else(|| format!("unknown type {}", name))?;
+    let body_start = start + code[start..].find('{').expect("Struct has body") + 1;
+    let body_end = body_start + code[body_start..].find('}').expect("Struct body is closed");
+    let object = value
+        .as_object()
+        .ok_or_else(|| format!("{} is not an object for {}", value, name))?;
+
+    let mut rest = &code[body_start..body_end];
+    while let Some(start) = rest.find("pub ") {
+        let attributes = &rest[..start];
+        let name_start = start + "pub ".len();
+        let name_end = name_start + rest[name_start..].find(" : ").expect("Field has type");
+        let type_start = name_end + " : ".len();
+        let type_end = type_start + type_len(&rest[type_start..]);
+        let field_type = rest[type_start..type_end].trim();
+
+        let wire_name = match attributes.find("rename = \"") {
+            Some(rename) => {
+                let rename = &attributes[rename + "rename = \"".len()..];
+                rename[..rename.find('"').expect("Rename is closed")].to_owned()
+            }
+            None => rest[name_start..name_end].trim().to_owned(),
+        };
+        match object.get(&wire_name) {
+            Some(field) => check_default(code, field_type, field)?,
+            None if field_type.starts_with("Option <") || attributes.contains("default") => {}
+            None => return Err(format!("{} misses the field {}", value, wire_name)),
+        }
+        rest = &rest[type_end..];
+    }
+
+    Ok(())
+}
@@ -0,0 +1,229 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+use std::{fs::File, path::PathBuf};
+
+use convert_case::{Case, Casing};
+use serde_json::Value;
+
+use crate::extras_generator::field_name;
+
+/// A string property which is restricted to a fixed set of values
+struct EnumProperty {
+    object_src: String,
+    property: String,
+    values: Vec<String>,
+}
+
+/// Replaces the `String` type of enum properties with generated enums.
+/// Unknown values are kept in an `Other` variant for forward compatibility.
+pub fn apply(code: String, schema_files: &[PathBuf]) -> String {
+    let mut code = code;
+    let mut enums = "".to_owned();
+
+    for file in schema_files {
+        let schema: Value = serde_json::from_reader(
+            File::open(file)
+                .unwrap_or_else(|e| panic!("Open schema file {}: {}", file.display(), e)),
+        )
+        .unwrap_or_else(|e| panic!("Parse JSON schema {}: {}", file.display(), e));
+
+        let mut properties = Vec::new();
+        find_enum_properties(&schema, file.display().to_string(), &mut properties);
+
+        for property in properties {
+            if let Some(enum_code) = replace_property_type(&mut code, &enums, &property) {
+                enums += &enum_code;
+            }
+        }
+    }
+
+    code + &enums
+}
+
+fn find_enum_properties(schema: &Value, src: String, found: &mut Vec<EnumProperty>) {
+    if let Some(items) = schema.get("items") {
+        find_enum_properties(items, format!("{}/items", src), found);
+    }
+
+    let properties = match schema.get("properties").and_then(Value::as_object) {
+        Some(properties) => properties,
+        None => return,
+    };
+
+    for (name, property) in properties {
+        let values: Option<Vec<String>> =
+            property
+                .get("enum")
+                .and_then(Value::as_array)
+                .and_then(|values| {
+                    values
+                        .iter()
+                        .map(|value| value.as_str().map(str::to_owned))
+                        .collect()
+                });
+
+        match (property.get("type").and_then(Value::as_str), values) {
+            (Some("string"), Some(values)) if !values.is_empty() => found.push(EnumProperty {
+                object_src: src.clone(),
+                property: name.clone(),
+                values,
+            }),
+            _ => find_enum_properties(property, format!("{}/properties/{}", src, name), found),
+        }
+    }
+}
+
+fn replace_property_type(
+    code: &mut String,
+    enums: &str,
+    property: &EnumProperty,
+) -> Option<String> {
+    let doc = format!("\"Generated from {}\"]", property.object_src);
+    let doc_start = code.find(&doc)?;
+    let struct_start = doc_start + code[doc_start..].find("pub struct ")?;
+    // A struct shared with an earlier one is an alias, which gets what the shared struct gets
+    if code[doc_start..struct_start].contains("pub type ") {
+        return None;
+    }
+    let name_start = struct_start + "pub struct ".len();
+    let body_start = name_start + code[name_start..].find('{')?;
+    let body_end = body_start + code[body_start..].find('}')?;
+    let struct_name = code[name_start..body_start].trim().to_owned();
+
+    let enum_name = format!("{}{}", struct_name, property.property.to_case(Case::Pascal));
+    // The name may be taken by a struct or by an enum generated for another property
+    let taken = |code: &str| {
+        ["struct", "type", "enum"]
+            .iter()
+            .any(|item| code.contains(&format!("pub {} {} ", item, enum_name)))
+    };
+    if taken(code) || taken(enums) {
+        return None;
+    }
+    let variants = variant_names(&property.values)?;
+
+    let field = format!("pub {} : ", field_name(&property.property));
+    let body = &code[body_start..body_end];
+    let field_start = body_start + body.find(&field)? + field.len();
+    let (old_type, new_type) = if code[field_start..].starts_with("String") {
+        ("String", enum_name.clone())
+    } else if code[field_start..].starts_with("Option < String >") {
+        ("Option < String >", format!("Option < {} >", enum_name))
+    } else {
+        return None;
+    };
+    code.replace_range(field_start..field_start + old_type.len(), &new_type);
+
+    Some(generate_enum(&enum_name, &variants, &property.values))
+}
+
+fn variant_names(values: &[String]) -> Option<Vec<String>> {
+    let mut names: Vec<String> = Vec::new();
+
+    for value in values {
+        let name: String = value
+            .to_case(Case::Pascal)
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .collect();
+        let name = match name.chars().next() {
+            Some(c) if c.is_ascii_digit() => format!("Value{}", name),
+            Some(_) => name,
+            None => return None,
+        };
+
+        if name == "Other" || names.contains(&name) {
+            return None;
+        }
+        names.push(name);
+    }
+
+    Some(names)
+}
+
+fn generate_enum(name: &str, variants: &[String], values: &[String]) -> String {
+    let mut enum_variants = "".to_owned();
+    let mut as_str = "".to_owned();
+    let mut from_str = "".to_owned();
+
+    for (variant, value) in variants.iter().zip(values) {
+        enum_variants += &format!("{},", variant);
+        as_str += &format!("{}::{} => {:?},", name, variant, value);
+        from_str += &format!("{:?} => {}::{},", value, name, variant);
+    }
+
+    format!(
+        "
+        #[derive(Clone, PartialEq, Eq, Debug)]
+        pub enum {name} {{
+            {enum_variants}
+            Other(String),
+        }}
+
+        impl {name} {{
+            pub fn as_str(&self) -> &str {{
+                match self {{
+                    {as_str}
+                    {name}::Other(value) => value,
+                }}
+            }}
+        }}
+
+        impl From<String> for {name} {{
+            fn from(value: String) -> Self {{
+                match value.as_str() {{
+                    {from_str}
+                    _ => {name}::Other(value),
+                }}
+            }}
+        }}
+
+        impl Serialize for {name} {{
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {{
+                serializer.serialize_str(self.as_str())
+            }}
+        }}
+
+        impl<'de> Deserialize<'de> for {name} {{
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {{
+                String::deserialize(deserializer).map(Self::from)
+            }}
+        }}
+
+        #[cfg(feature = \"arbitrary\")]
+        impl<'a> arbitrary::Arbitrary<'a> for {name} {{
+            fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {{
+                let values = [{values}];
+                Ok(Self::from((*u.choose(&values)?).to_owned()))
+            }}
+        }}
+
+        /// Any string is accepted, the known values are listed as examples
+        #[cfg(feature = \"schemars\")]
+        impl schemars::JsonSchema for {name} {{
+            fn schema_name() -> std::borrow::Cow<'static, str> {{
+                \"{name}\".into()
+            }}
+
+            fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {{
+                schemars::json_schema!({{
+                    \"type\": \"string\",
+                    \"examples\": [{values}]
+                }})
+            }}
+        }}
+        ",
+        name = name,
+        enum_variants = enum_variants,
+        as_str = as_str,
+        from_str = from_str,
+        values = values
+            .iter()
+            .map(|value| format!("{:?}", value))
+            .collect::<Vec<_>>()
+            .join(", "),
+    )
+}
@@ -4,26 +4,60 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
  */
 use std::{
-    fs::File,
+    env,
+    fs::{self, File},
     path::{Path, PathBuf},
 };
 
 use convert_case::{Case, Casing};
-use serde_json::Value;
+use serde_json::{Map, Value};
 
-pub fn generate(path: &Path) -> String {
+/// `types` is the generated code of the types, which is used to look up field types.
+/// `types_path` is the module path the extras import the types from.
+pub fn generate(path: &Path, types: &str, types_path: &str) -> String {
     let message_schemas = read_message_schemas(path);
-    generate_extras(&message_schemas)
+    generate_extras(
+        path,
+        &message_schemas,
+        types,
+        types_path,
+        &discriminator(path),
+    )
 }
+
+/// An invocation of `macro_name!(snake_case_name, Name, NameMessageData)` for every message type,
+/// e.g. to generate a test per message type
+pub fn message_list(path: &Path, macro_name: &str) -> String {
+    read_message_schemas(path)
+        .iter()
+        .map(|schema| {
+            format!(
+                "{0}!({1}, {2}, {2}MessageData);\n",
+                macro_name,
+                schema.name().to_case(Case::Snake),
+                schema.name()
+            )
+        })
+        .collect()
+}
+
 struct MessageSchema {
     path: PathBuf,
+    /// The path relative to the schema repository, as referenced by the top-level schema
+    file: String,
     schema: Value,
+    discriminator: String,
 }
 
 impl MessageSchema {
-    pub fn new(path: PathBuf) -> Self {
+    pub fn new(path: PathBuf, file: String, discriminator: String) -> Self {
         let schema = Self::schema(&path);
-        Self { path, schema }
+        Self {
+            path,
+            file,
+            schema,
+            discriminator,
+        }
     }
 
     fn schema(path: &Path) -> Value {
@@ -50,24 +84,268 @@ impl MessageSchema {
             .expect("Schema has properties")
             .as_object()
             .expect("Schema properties is object")
-            .get("messageType")
-            .expect("Schema has messageType")
+            .get(&self.discriminator)
+            .expect("Schema has discriminator")
             .as_object()
-            .expect("Schema messageType is object")
+            .expect("Schema discriminator is object")
             .get("const")
-            .expect("Schema messageType is const")
+            .expect("Schema discriminator is const")
             .as_i64()
-            .expect("Schema messageType is integer")
+            .expect("Schema discriminator is integer")
+    }
+
+    /// Whether the message fields are nested under `data` in the schema
+    ///
+    /// Fields defined at the top level are flattened into a data struct by the `flatten_generator`,
+    /// so the generated code accesses them through `data` either way.
+    pub fn nested(&self) -> bool {
+        self.schema.pointer("/properties/data").is_some()
+    }
+
+    fn data(&self) -> &Map<String, Value> {
+        let root = self.schema.as_object().expect("Schema root is object");
+        match self.nested() {
+            true => root["properties"]["data"]
+                .as_object()
+                .expect("Schema data is object"),
+            false => root,
+        }
+    }
+
+    pub fn data_properties(&self) -> Vec<String> {
+        match self.data().get("properties") {
+            Some(properties) => properties
+                .as_object()
+                .expect("Schema data properties is object")
+                .keys()
+                .filter(|name| self.nested() || **name != self.discriminator)
+                .cloned()
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    pub fn has_property(&self, property: &str) -> bool {
+        self.data_properties().iter().any(|name| name == property)
+    }
+
+    pub fn property_type(&self, property: &str) -> Option<&str> {
+        self.data()
+            .get("properties")
+            .and_then(|properties| properties.get(property))
+            .and_then(|property| property.get("type"))
+            .and_then(|type_| type_.as_str())
+    }
+
+    /// Rough stack size of the data struct, nested objects count as a single pointer sized field
+    pub fn estimated_size(&self) -> usize {
+        self.data_properties()
+            .iter()
+            .map(|property| match self.property_type(property) {
+                Some("boolean") => 1,
+                Some("integer") | Some("number") => 8,
+                _ => 24,
+            })
+            .sum()
+    }
+
+    /// Whether the variant of the message is boxed in the `Message` enum
+    pub fn boxed(&self) -> bool {
+        env::var_os("CARGO_FEATURE_BOXED_VARIANTS").is_some()
+            && self.estimated_size() > BOXED_VARIANT_THRESHOLD
+    }
+
+    /// Whether the message is sent from the gateway to the plugin
+    ///
+    /// A `direction` of `toPlugin` or `toGateway` in the schema file, e.g. from the schema overrides,
+    /// takes precedence over `MESSAGE_DIRECTIONS`. Messages in neither are guessed from their name
+    /// with a build warning: commands and requests go to the plugin, everything else to the gateway.
+    pub fn to_plugin(&self) -> bool {
+        let name = self.name();
+        match self.schema.get("direction").and_then(Value::as_str) {
+            Some("toPlugin") => return true,
+            Some("toGateway") => return false,
+            Some(direction) => panic!(
+                "Direction {} of {} is neither toPlugin nor toGateway",
+                direction, self.file
+            ),
+            None => {}
+        }
+        match MESSAGE_DIRECTIONS
+            .iter()
+            .find(|(message, _)| *message == name)
+        {
+            Some((_, to_plugin)) => *to_plugin,
+            None => {
+                let to_plugin = name.ends_with("Command") || name.ends_with("Request");
+                println!(
+                    "cargo:warning=No direction for message {} of {}, assuming {}",
+                    name,
+                    self.file,
+                    if to_plugin { "toPlugin" } else { "toGateway" }
+                );
+                to_plugin
+            }
+        }
+    }
+
+    pub fn is_required(&self, property: &str) -> bool {
+        match self.data().get("required") {
+            Some(required) => required
+                .as_array()
+                .expect("Schema data required is array")
+                .iter()
+                .any(|name| name.as_str() == Some(property)),
+            None => false,
+        }
     }
 }
 
-fn read_message_schemas(path: &Path) -> Vec<MessageSchema> {
-    let schema: Value = serde_json::from_reader(
-        File::open(path).expect(&format!("Open schema file {}", path.display())),
-    )
-    .expect(&format!("Parse JSON schema {}", path.display()));
+/// Data structs estimated above this size in bytes are boxed with the `boxed-variants` feature
+const BOXED_VARIANT_THRESHOLD: usize = 64;
+
+/// Whether each message is sent from the gateway to the plugin (`true`) or the other way round
+///
+/// The schema has no direction metadata, so the messages of the schema repository are listed here,
+/// see [`MessageSchema::to_plugin`] for the others.
+const MESSAGE_DIRECTIONS: &[(&str, bool)] = &[
+    ("PluginRegisterRequest", false),
+    ("PluginRegisterResponse", true),
+    ("PluginUnloadRequest", true),
+    ("PluginUnloadResponse", false),
+    ("PluginErrorNotification", false),
+    ("AdapterAddedNotification", false),
+    ("AdapterCancelPairingCommand", true),
+    ("AdapterPairingPromptNotification", false),
+    ("AdapterRemoveDeviceRequest", true),
+    ("AdapterRemoveDeviceResponse", false),
+    ("AdapterStartPairingCommand", true),
+    ("AdapterUnloadRequest", true),
+    ("AdapterUnloadResponse", false),
+    ("AdapterUnpairingPromptNotification", false),
+    ("ApiHandlerAddedNotification", false),
+    ("ApiHandlerUnloadRequest", true),
+    ("ApiHandlerUnloadResponse", false),
+    ("ApiHandlerApiRequest", true),
+    ("ApiHandlerApiResponse", false),
+    ("DeviceAddedNotification", false),
+    ("DeviceActionStatusNotification", false),
+    ("DeviceConnectedStateNotification", false),
+    ("DeviceDebugCommand", true),
+    ("DeviceEventNotification", false),
+    ("DevicePropertyChangedNotification", false),
+    ("DeviceRemoveActionRequest", true),
+    ("DeviceRemoveActionResponse", false),
+    ("DeviceRequestActionRequest", true),
+    ("DeviceRequestActionResponse", false),
+    ("DeviceSavedNotification", true),
+    ("DeviceSetCredentialsRequest", true),
+    ("DeviceSetCredentialsResponse", false),
+    ("DeviceSetPinRequest", true),
+    ("DeviceSetPinResponse", false),
+    ("DeviceSetPropertyCommand", true),
+    ("NotifierAddedNotification", false),
+    ("NotifierUnloadRequest", true),
+    ("NotifierUnloadResponse", false),
+    ("OutletAddedNotification", false),
+    ("OutletRemovedNotification", false),
+    ("OutletNotifyRequest", true),
+    ("OutletNotifyResponse", false),
+    ("MockAdapterAddDeviceRequest", true),
+    ("MockAdapterAddDeviceResponse", false),
+    ("MockAdapterClearStateRequest", true),
+    ("MockAdapterClearStateResponse", false),
+    ("MockAdapterPairDeviceCommand", true),
+    ("MockAdapterRemoveDeviceRequest", true),
+    ("MockAdapterRemoveDeviceResponse", false),
+    ("MockAdapterUnpairDeviceCommand", true),
+];
+
+const RUST_KEYWORDS: [&str; 52] = [
+    "as", "break", "const", "continue", "crate", "else", "enum", "extern", "false", "fn", "for",
+    "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return",
+    "self", "Self", "static", "struct", "super", "trait", "true", "type", "unsafe", "use", "where",
+    "while", "async", "await", "dyn", "abstract", "become", "box", "do", "final", "macro",
+    "override", "priv", "typeof", "unsized", "virtual", "yield", "try", "union",
+];
+
+/// Mirrors the field naming of `jsonschema_code_generator`
+pub fn field_name(property: &str) -> String {
+    let name = property
+        .chars()
+        .flat_map(|c| match c.is_uppercase() {
+            true => vec![' ', c],
+            false => vec![c],
+        })
+        .collect::<String>()
+        .replace("@", " at ")
+        .replace("$", " dollar ")
+        .to_case(Case::Snake);
+
+    match RUST_KEYWORDS.contains(&name.as_str()) {
+        true => name + "_",
+        false => name,
+    }
+}
+
+/// Looks up the type of a field in the generated code of the types
+pub fn field_type(types: &str, object_src: &str, property: &str) -> Option<String> {
+    let doc = format!("\"Generated from {}\"]", object_src);
+    let doc_start = types.find(&doc)?;
+    let body_start = doc_start + types[doc_start..].find('{')?;
+    let body_end = body_start + types[body_start..].find('}')?;
+
+    let field = format!("pub {} : ", field_name(property));
+    let type_start = body_start + types[body_start..body_end].find(&field)? + field.len();
+    let type_end = type_start + type_len(&types[type_start..body_end]);
+    Some(types[type_start..type_end].trim().to_owned())
+}
+
+/// Length of the field type up to the comma which ends the field, the last field has no comma
+pub fn type_len(code: &str) -> usize {
+    let mut depth = 0;
+
+    for (index, c) in code.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => depth -= 1,
+            ',' if depth == 0 => return index,
+            _ => {}
+        }
+    }
+
+    code.len()
+}
+
+/// The property which tells the message types apart, `messageType` in the upstream schema
+///
+/// It is the property of the top-level schema which every message schema fixes with a `const`.
+pub fn discriminator(path: &Path) -> String {
+    let schema = MessageSchema::schema(path);
+    let messages: Vec<Value> = message_files(path)
+        .iter()
+        .map(|file| MessageSchema::schema(&path.parent().expect("Path parent").join(file)))
+        .collect();
 
     schema
+        .get("properties")
+        .and_then(Value::as_object)
+        .expect("Schema has properties")
+        .keys()
+        .find(|key| {
+            messages.iter().all(|message| {
+                message
+                    .pointer(&format!("/properties/{}/const", key))
+                    .is_some()
+            })
+        })
+        .expect("Schema has a property which is const in every message")
+        .clone()
+}
+
+/// The message schema files referenced by the top-level schema
+pub fn message_files(path: &Path) -> Vec<String> {
+    MessageSchema::schema(path)
         .as_object()
         .expect("Schema root is object")
         .get("properties")
@@ -82,16 +360,30 @@ fn read_message_schemas(path: &Path) -> Vec<MessageSchema> {
         .expect("Schema has oneOf")
         .as_array()
         .expect("Schema oneOf is array")
-        .into_iter()
+        .iter()
         .map(|obj| {
-            let file = obj
-                .as_object()
+            obj.as_object()
                 .expect("Schema oneOf entry is object")
                 .get("$ref")
                 .expect("Schema has $ref")
                 .as_str()
-                .expect("Schema $ref is string");
-            MessageSchema::new(path.parent().expect("Path parent").join(file))
+                .expect("Schema $ref is string")
+                .to_owned()
+        })
+        .collect()
+}
+
+fn read_message_schemas(path: &Path) -> Vec<MessageSchema> {
+    let discriminator = discriminator(path);
+
+    message_files(path)
+        .into_iter()
+        .map(|file| {
+            MessageSchema::new(
+                path.parent().expect("Path parent").join(&file),
+                file,
+                discriminator.clone(),
+            )
         })
         .collect()
 }
@@ -110,14 +402,20 @@ macro_rules! iterate {
     }};
 }
 
-fn generate_extras(schemas: &Vec<MessageSchema>) -> String {
+fn generate_extras(
+    path: &Path,
+    schemas: &Vec<MessageSchema>,
+    types: &str,
+    types_path: &str,
+    discriminator: &str,
+) -> String {
     format!(
         "
-        use std::{{fmt::{{self, Display, Formatter}}, str::FromStr}};
+        use std::{{convert::TryFrom, fmt::{{self, Display, Formatter}}, str::FromStr}};
 
-        use serde::{{ser::{{self, Serializer}}, Serialize, Deserialize}};
+        use serde::{{de::{{DeserializeOwned, Deserializer}}, ser::{{self, Serializer}}, Serialize, Deserialize}};
 
-        use crate::types::*;
+        use {types_path}::*;
 
         pub trait MessageType {{
             const MESSAGE_ID: i64;
@@ -130,13 +428,139 @@ fn generate_extras(schemas: &Vec<MessageSchema>) -> String {
 
         #[derive(Serialize, Deserialize, Debug)]
         pub struct GenericMessage {{
-            #[serde(rename = \"messageType\")]           
-            message_type: i64
+            #[serde(rename = {discriminator:?})]
+            pub(crate) message_type: i64
+        }}
+
+        impl GenericMessage {{
+            pub fn message_type(&self) -> i64 {{
+                self.message_type
+            }}
+
+            /// Parses the input this was peeked from without deserializing its envelope again
+            ///
+            /// `raw` has to be that input, a message type other than the peeked one fails.
+            pub fn into_message(self, raw: &str) -> Result<Message, Error> {{
+                serde_json::from_str(raw)
+                    .map_err(|e| invalid_json_error(e, raw))
+                    .and_then(|value: serde_json::Value| {{
+                        match value.get(MESSAGE_TYPE_KEY).and_then(serde_json::Value::as_i64) {{
+                            Some(code) if code == self.message_type => {{
+                                Message::dispatch_type(code, raw, raw.len(), value)
+                            }}
+                            code => Err(parse_error(
+                                Error::new(format!(
+                                    \"Peeked message type {{}} but the input has message type {{}}\",
+                                    self.message_type,
+                                    code.map_or_else(|| \"none\".to_owned(), |code| code.to_string())
+                                )),
+                                raw.len(),
+                            )),
+                        }}
+                    }})
+                    .map_err(|e| raw_input_error(e, raw))
+            }}
         }}
 
+        /// The property of a message which holds its message type
+        pub const MESSAGE_TYPE_KEY: &str = {discriminator:?};
+
         #[derive(Debug)]
         pub struct Error {{
             message: String,
+            source: Option<std::io::Error>,
+            raw: Option<String>,
+            trailing: Option<usize>,
+            unknown_message_type: Option<i64>,
+        }}
+
+        /// Maximum number of bytes of the offending input kept in an error
+        pub const RAW_INPUT_LIMIT: usize = 256;
+
+        impl Error {{
+            pub(crate) fn new(message: String) -> Self {{
+                Self {{ message, source: None, raw: None, trailing: None, unknown_message_type: None }}
+            }}
+
+            /// An error with the given message, e.g. for the parse functions of a
+            /// [`MessageRegistry`](crate::MessageRegistry)
+            pub fn custom(message: impl std::fmt::Display) -> Self {{
+                Self::new(message.to_string())
+            }}
+
+            pub(crate) fn with_source(message: String, source: std::io::Error) -> Self {{
+                Self {{ message, source: Some(source), raw: None, trailing: None, unknown_message_type: None }}
+            }}
+
+            pub(crate) fn unknown_message_type(message_type: i64) -> Self {{
+                let mut error = Self::new(format!(\"Unknown message type {{}}\", message_type));
+                error.unknown_message_type = Some(message_type);
+                error
+            }}
+
+            /// Prefixes the message with where the error occurred
+            pub(crate) fn context(mut self, context: &str) -> Self {{
+                self.message = format!(\"{{}}: {{}}\", context, self.message);
+                self
+            }}
+
+            /// Attaches the first `RAW_INPUT_LIMIT` bytes of the offending input
+            ///
+            /// The input is not part of the `Display` output, it has to be logged explicitly.
+            #[must_use]
+            pub fn with_raw(mut self, input: &str) -> Self {{
+                let mut end = input.len().min(RAW_INPUT_LIMIT);
+                while !input.is_char_boundary(end) {{
+                    end -= 1;
+                }}
+                self.raw = Some(input[..end].to_owned());
+                self
+            }}
+
+            /// The offending input, parse errors carry it with the `raw-input-errors` feature
+            pub fn raw(&self) -> Option<&str> {{
+                self.raw.as_deref()
+            }}
+
+            /// The number of bytes forming a valid JSON value if the input continues after it
+            ///
+            /// A stream without delimiters can skip these bytes and resume at the trailing data.
+            pub fn trailing_data(&self) -> Option<usize> {{
+                self.trailing
+            }}
+
+            /// Whether the input is a well-formed message of a type which is not known
+            pub fn is_unknown_message_type(&self) -> bool {{
+                self.unknown_message_type.is_some()
+            }}
+
+            /// The message type of the input if that type is not known
+            pub fn unknown_message_type_id(&self) -> Option<i64> {{
+                self.unknown_message_type
+            }}
+        }}
+
+        impl std::error::Error for Error {{
+            fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {{
+                self.source.as_ref().map(|e| e as _)
+            }}
+        }}
+
+        impl From<std::io::Error> for Error {{
+            fn from(e: std::io::Error) -> Self {{
+                Self::with_source(format!(\"IO error: {{}}\", e), e)
+            }}
+        }}
+
+        /// Keeps the kind of IO errors, all other errors are `InvalidData`
+        impl From<Error> for std::io::Error {{
+            fn from(e: Error) -> Self {{
+                let kind = match &e.source {{
+                    Some(source) => source.kind(),
+                    None => std::io::ErrorKind::InvalidData,
+                }};
+                std::io::Error::new(kind, e)
+            }}
         }}
 
         impl Display for Error {{
@@ -147,66 +571,604 @@ fn generate_extras(schemas: &Vec<MessageSchema>) -> String {
 
         {schemafy_impl}
 
-        #[derive(Debug)]
+        {message_conversions}
+
+        {incoming}
+
+        {plugin_error}
+
+        {for_plugin}
+
+        {respond}
+
+        impl Message {{
+            pub fn correlation_id(&self) -> Option<i64> {{
+                match self {{
+                    {message_correlation_id}
+                    Message::Unknown {{ raw, .. }} => raw.pointer(\"/data/messageId\").and_then(serde_json::Value::as_i64),
+                }}
+            }}
+
+            /// The plugin id, `None` only for a message of an unknown type without one
+            ///
+            /// `MessageBase::plugin_id` returns an empty string in that case.
+            pub fn plugin_id_opt(&self) -> Option<&str> {{
+                match self {{
+                    Message::Unknown {{ raw, .. }} => raw.pointer(\"/data/pluginId\").and_then(serde_json::Value::as_str),
+                    msg => Some(msg.plugin_id()),
+                }}
+            }}
+
+            pub fn device_id(&self) -> Option<&str> {{
+                match self {{
+                    {message_device_id}
+                    Message::Unknown {{ raw, .. }} => raw.pointer(\"/data/deviceId\").and_then(serde_json::Value::as_str),
+                }}
+            }}
+
+            pub fn adapter_id(&self) -> Option<&str> {{
+                match self {{
+                    {message_adapter_id}
+                    Message::Unknown {{ raw, .. }} => raw.pointer(\"/data/adapterId\").and_then(serde_json::Value::as_str),
+                }}
+            }}
+
+            /// Name and value of the property the message refers to
+            pub fn property(&self) -> Option<(&str, &PropertyValue)> {{
+                match self {{
+                    {message_property}
+                    Message::Unknown {{ .. }} => None,
+                }}
+            }}
+
+            /// Replaces the ids for which `f` returns a new id, for proxying between id spaces
+            pub fn rewrite_ids(&mut self, f: impl Fn(IdKind, &str) -> Option<String>) {{
+                match self {{
+                    {message_rewrite_ids}
+                    Message::Unknown {{ raw, .. }} => {{
+                        for (pointer, kind) in &[
+                            (\"/data/pluginId\", IdKind::Plugin),
+                            (\"/data/adapterId\", IdKind::Adapter),
+                            (\"/data/deviceId\", IdKind::Device),
+                        ] {{
+                            if let Some(serde_json::Value::String(id)) = raw.pointer_mut(pointer) {{
+                                if let Some(new_id) = f(*kind, id) {{
+                                    *id = new_id;
+                                }}
+                            }}
+                        }}
+                    }}
+                }}
+            }}
+
+            pub fn set_plugin_id(&mut self, plugin_id: &str) {{
+                match self {{
+                    {message_set_plugin_id}
+                    Message::Unknown {{ raw, .. }} => {{
+                        if let Some(serde_json::Value::Object(data)) = raw.get_mut(\"data\") {{
+                            data.insert(\"pluginId\".to_owned(), plugin_id.into());
+                        }}
+                    }}
+                }}
+            }}
+
+            /// Does nothing if the message has no correlation id
+            pub fn set_correlation_id(&mut self, id: i64) {{
+                match self {{
+                    {message_set_correlation_id}
+                    Message::Unknown {{ raw, .. }} => {{
+                        if let Some(message_id) = raw.pointer_mut(\"/data/messageId\") {{
+                            *message_id = id.into();
+                        }}
+                    }}
+                }}
+            }}
+        }}
+
         pub enum Message {{
             {message_enum}
+            /// A message type unknown to this version of the crate, see `Message::from_str_or_unknown`
+            Unknown {{
+                message_type: i64,
+                raw: serde_json::Value,
+            }},
+        }}
+
+        #[cfg(feature = \"arbitrary\")]
+        impl<'a> arbitrary::Arbitrary<'a> for Message {{
+            fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {{
+                Ok(match *u.choose(&MESSAGE_IDS)? {{
+                    {message_arbitrary}
+                    _ => unreachable!(\"MESSAGE_IDS only contains known message types\"),
+                }})
+            }}
+        }}
+
+        /// Generates JSON values which survive a serialization round trip
+        #[cfg(feature = \"arbitrary\")]
+        pub(crate) trait ArbitraryJson: Sized {{
+            fn arbitrary_json(u: &mut arbitrary::Unstructured) -> arbitrary::Result<Self>;
+        }}
+
+        #[cfg(feature = \"arbitrary\")]
+        pub(crate) fn arbitrary_json<T: ArbitraryJson>(
+            u: &mut arbitrary::Unstructured,
+        ) -> arbitrary::Result<T> {{
+            T::arbitrary_json(u)
+        }}
+
+        #[cfg(feature = \"arbitrary\")]
+        impl ArbitraryJson for f64 {{
+            fn arbitrary_json(u: &mut arbitrary::Unstructured) -> arbitrary::Result<Self> {{
+                let value: f64 = u.arbitrary()?;
+                Ok(if value.is_finite() {{ value }} else {{ 0.0 }})
+            }}
+        }}
+
+        #[cfg(feature = \"arbitrary\")]
+        impl ArbitraryJson for serde_json::Number {{
+            fn arbitrary_json(u: &mut arbitrary::Unstructured) -> arbitrary::Result<Self> {{
+                Ok(u.arbitrary::<i64>()?.into())
+            }}
+        }}
+
+        #[cfg(feature = \"arbitrary\")]
+        impl ArbitraryJson for serde_json::Value {{
+            fn arbitrary_json(u: &mut arbitrary::Unstructured) -> arbitrary::Result<Self> {{
+                Ok(match u.choose_index(4)? {{
+                    0 => serde_json::Value::Null,
+                    1 => serde_json::Value::Bool(u.arbitrary()?),
+                    2 => serde_json::Value::Number(serde_json::Number::arbitrary_json(u)?),
+                    _ => serde_json::Value::String(u.arbitrary()?),
+                }})
+            }}
+        }}
+
+        #[cfg(all(feature = \"arbitrary\", feature = \"raw-value\"))]
+        impl ArbitraryJson for Box<serde_json::value::RawValue> {{
+            fn arbitrary_json(u: &mut arbitrary::Unstructured) -> arbitrary::Result<Self> {{
+                let value = serde_json::Value::arbitrary_json(u)?;
+                Ok(serde_json::value::to_raw_value(&value).expect(\"JSON value is serializable\"))
+            }}
+        }}
+
+        #[cfg(feature = \"arbitrary\")]
+        impl<T: ArbitraryJson> ArbitraryJson for Option<T> {{
+            fn arbitrary_json(u: &mut arbitrary::Unstructured) -> arbitrary::Result<Self> {{
+                match u.arbitrary()? {{
+                    true => T::arbitrary_json(u).map(Some),
+                    false => Ok(None),
+                }}
+            }}
+        }}
+
+        #[cfg(feature = \"arbitrary\")]
+        impl<T: ArbitraryJson> ArbitraryJson for Vec<T> {{
+            fn arbitrary_json(u: &mut arbitrary::Unstructured) -> arbitrary::Result<Self> {{
+                let len = u.int_in_range(0..=4)?;
+                (0..len).map(|_| T::arbitrary_json(u)).collect()
+            }}
+        }}
+
+        #[cfg(feature = \"arbitrary\")]
+        impl<T: ArbitraryJson> ArbitraryJson for std::collections::BTreeMap<String, T> {{
+            fn arbitrary_json(u: &mut arbitrary::Unstructured) -> arbitrary::Result<Self> {{
+                let len = u.int_in_range(0..=4)?;
+                (0..len)
+                    .map(|_| Ok((u.arbitrary()?, T::arbitrary_json(u)?)))
+                    .collect()
+            }}
+        }}
+
+        /// The kind of id passed to the mapping of `Message::rewrite_ids`
+        #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+        pub enum IdKind {{
+            Plugin,
+            Adapter,
+            Device,
+        }}
+
+        /// The type of untyped schema fields like property values
+        #[cfg(not(feature = \"raw-value\"))]
+        pub type PropertyValue = serde_json::Value;
+
+        /// The type of untyped schema fields like property values
+        #[cfg(feature = \"raw-value\")]
+        pub type PropertyValue = Box<serde_json::value::RawValue>;
+
+        pub const MESSAGE_TYPE_COUNT: usize = {message_type_count};
+
+        pub const MESSAGE_IDS: [i64; MESSAGE_TYPE_COUNT] = [{message_ids}];
+
+        /// The name of each message type and the schema file it is generated from, relative to the schema repository
+        pub const MESSAGE_SCHEMA_FILES: [(&str, &str); MESSAGE_TYPE_COUNT] = [{message_schema_files}];
+
+        /// Every file of the schema repository and its contents, to resolve the references of the message schemas
+        #[cfg(feature = \"validation\")]
+        pub const SCHEMA_REPOSITORY_FILES: &[(&str, &str)] = &[{schema_repository_files}];
+
+        // The matches over Message have no wildcard arm, so the compiler checks them for completeness.
+        // from_str matches on the id instead, a duplicate id would silently shadow a message type.
+        const _: () = {{
+            let mut i = 0;
+            while i < MESSAGE_TYPE_COUNT {{
+                let mut j = i + 1;
+                while j < MESSAGE_TYPE_COUNT {{
+                    if MESSAGE_IDS[i] == MESSAGE_IDS[j] {{
+                        panic!(\"Duplicate message type\");
+                    }}
+                    j += 1;
+                }}
+                i += 1;
+            }}
+        }};
+
+        impl AsRef<Message> for Message {{
+            fn as_ref(&self) -> &Message {{
+                self
+            }}
+        }}
+
+        impl fmt::Debug for Message {{
+            fn fmt(&self, f: &mut Formatter) -> fmt::Result {{
+                match self {{
+                    {message_debug}
+                    Message::Unknown {{ message_type, raw }} => f
+                        .debug_struct(\"Message::Unknown\")
+                        .field(\"message_type\", message_type)
+                        .field(\"raw\", raw)
+                        .finish(),
+                }}
+            }}
         }}
 
         impl MessageBase for Message {{
             fn message_id(&self) -> i64 {{
                 match self {{
                     {message_message_id}
+                    Message::Unknown {{ message_type, .. }} => *message_type,
                 }}
             }}
             fn plugin_id(&self) -> &str {{
                 match self {{
                     {message_plugin_id}
+                    Message::Unknown {{ raw, .. }} => raw
+                        .pointer(\"/data/pluginId\")
+                        .and_then(serde_json::Value::as_str)
+                        .unwrap_or_default(),
                 }}
             }}
         }}
         
-        impl FromStr for Message {{
-            type Err = Error;
+        impl Message {{
+            pub fn name(&self) -> &'static str {{
+                match self {{
+                    {message_name}
+                    Message::Unknown {{ .. }} => \"Unknown\",
+                }}
+            }}
 
-            fn from_str(s: &str) -> Result<Self, Self::Err> {{
-                let msg: GenericMessage = serde_json::from_str(s)
+            /// Whether the receiver answers the message with a response of the matching type
+            ///
+            /// Only requests with a response in the schema expect one, the correlation id links them.
+            pub fn requires_response(&self) -> bool {{
+                match self {{
+                    {message_requires_response}
+                    Message::Unknown {{ .. }} => false,
+                }}
+            }}
+
+            /// The JSON schema of the message type with the given id
+            pub fn schema_for_id(id: i64) -> Option<&'static str> {{
+                match id {{
+                    {message_schema}
+                    _ => None,
+                }}
+            }}
+
+            /// The name and JSON schema derived from the generated type of every message type
+            ///
+            /// Unlike [`Message::schemas`], these are derived with `schemars::schema_for!`,
+            /// so the references are resolved and the field types follow the enabled features.
+            #[cfg(feature = \"schemars\")]
+            pub fn derived_schemas() -> Vec<(&'static str, schemars::Schema)> {{
+                vec![
+                    {message_derived_schema}
+                ]
+            }}
+
+            fn parse(s: &str) -> Result<Self, Error> {{
+                serde_json::from_str(s)
+                    .map_err(|e| invalid_json_error(e, s))
+                    .and_then(|value| Self::dispatch(s, s.len(), value))
+                    .map_err(|e| raw_input_error(e, s))
+            }}
+
+            /// Parses a message from a mutable buffer, which `simd-json` uses as scratch space
+            #[cfg(all(feature = \"simd-json\", not(feature = \"raw-value\")))]
+            pub fn from_slice(bytes: &mut [u8]) -> Result<Self, Error> {{
+                // simd-json overwrites the buffer, so only the part of the input an error keeps is copied
+                let head = String::from_utf8_lossy(&bytes[..bytes.len().min(RAW_INPUT_LIMIT)]).into_owned();
+                let len = bytes.len();
+                let value: serde_json::Value = simd_json::serde::from_slice(bytes)
+                    .map_err(|e| parse_error(Error::new(format!(\"Invalid message: {{}}\", e)), len))
+                    .map_err(|e| raw_input_error(e, &head))?;
+                Self::dispatch(&head, len, value).map_err(|e| raw_input_error(e, &head))
+            }}
+
+            /// Parses a message from a buffer, the buffer is left unchanged without the `simd-json` feature
+            #[cfg(not(all(feature = \"simd-json\", not(feature = \"raw-value\"))))]
+            pub fn from_slice(bytes: &mut [u8]) -> Result<Self, Error> {{
+                let s = std::str::from_utf8(bytes)
+                    .map_err(|e| Error::new(format!(\"Invalid UTF-8: {{}}\", e)))?;
+                Self::from_str(s)
+            }}
+
+            /// Keeps messages of unknown types as `Message::Unknown` instead of failing
+            ///
+            /// `from_str` stays strict and rejects unknown message types.
+            pub fn from_str_or_unknown(s: &str) -> Result<Self, Error> {{
+                let value: serde_json::Value = serde_json::from_str(s)
+                    .map_err(|e| invalid_json_error(e, s))?;
+                let msg = GenericMessage::deserialize(&value)
+                    .map_err(|e| parse_error(Error::new(format!(\"Invalid message: {{}}\", e)), s.len()))?;
+                if MESSAGE_IDS.contains(&msg.message_type) {{
+                    Self::dispatch(s, s.len(), value)
+                }} else {{
+                    Ok(Message::Unknown {{
+                        message_type: msg.message_type,
+                        raw: value,
+                    }})
+                }}
+            }}
+
+            /// `len` is the length of the input, `s` can be a prefix of it if the input is not kept
+            fn dispatch(s: &str, len: usize, value: serde_json::Value) -> Result<Self, Error> {{
+                let msg = GenericMessage::deserialize(&value)
                     .map_err(|e| 
-                        Error {{ message: format!(\"Invalid message: {{}}\", e.to_string()).to_owned() }}
+                        parse_error(Error::new(format!(\"Invalid message: {{}}\", e.to_string()).to_owned()), len)
                     )?;
-                let code = msg.message_type;
-                match code {{
+                Self::dispatch_type(msg.message_type, s, len, value)
+            }}
+
+            /// Every parse of a message ends up here, so this is where it is traced and logged
+            fn dispatch_type(code: i64, s: &str, len: usize, value: serde_json::Value) -> Result<Self, Error> {{
+                traced(code, len, move || match code {{
                     {message_from_str}
-                    _ => Err(Error {{ message: \"Unknown message type\".to_owned() }}),
+                    _ => Err(Error::unknown_message_type(code)),
+                }})
+            }}
+
+            // Reuses the already parsed value instead of parsing the input a second time
+            #[cfg(not(feature = \"raw-value\"))]
+            fn decode<T: DeserializeOwned>(_s: &str, value: serde_json::Value) -> Result<T, Error> {{
+                deserialize_json(value)
+            }}
+
+            // A RawValue can only be deserialized from the original input
+            #[cfg(feature = \"raw-value\")]
+            fn decode<T: DeserializeOwned>(s: &str, _value: serde_json::Value) -> Result<T, Error> {{
+                deserialize_json(&mut serde_json::Deserializer::from_str(s))
+            }}
+        }}
+
+        /// Input which starts with a valid JSON value only fails to parse because of trailing data
+        fn invalid_json_error(e: serde_json::Error, s: &str) -> Error {{
+            let mut error = Error::new(format!(\"Invalid message: {{}}\", e));
+            let mut values = serde_json::Deserializer::from_str(s).into_iter::<serde::de::IgnoredAny>();
+            if let Some(Ok(_)) = values.next() {{
+                error.trailing = Some(values.byte_offset());
+            }}
+            parse_error(error, s.len())
+        }}
+
+        /// The name of the message type with the given id
+        #[cfg(any(feature = \"tracing\", feature = \"log\"))]
+        fn message_name(message_type: i64) -> Option<&'static str> {{
+            let index = MESSAGE_IDS.iter().position(|id| *id == message_type)?;
+            Some(MESSAGE_SCHEMA_FILES[index].0)
+        }}
+
+        /// Decodes a message of the given type within a span carrying its name and plugin id,
+        /// a failure is emitted as event
+        #[cfg(feature = \"tracing\")]
+        fn traced<T, F>(message_type: i64, len: usize, decode: F) -> Result<T, Error>
+        where
+            T: MessageBase,
+            F: FnOnce() -> Result<T, Error>,
+        {{
+            let span = tracing::debug_span!(
+                \"dispatch\",
+                message_type,
+                message = message_name(message_type).unwrap_or(\"Unknown\"),
+                plugin_id = tracing::field::Empty,
+            );
+            let _enter = span.enter();
+            let result = decode();
+            match &result {{
+                Ok(msg) => {{
+                    span.record(\"plugin_id\", msg.plugin_id());
                 }}
+                Err(e) => tracing::warn!(error = %e, \"Could not parse message\"),
+            }}
+            log_parsed(message_type, len, &result);
+            result
+        }}
+
+        #[cfg(not(feature = \"tracing\"))]
+        fn traced<T, F>(message_type: i64, len: usize, decode: F) -> Result<T, Error>
+        where
+            T: MessageBase,
+            F: FnOnce() -> Result<T, Error>,
+        {{
+            let result = decode();
+            log_parsed(message_type, len, &result);
+            result
+        }}
+
+        /// Logs the name and input length of a parsed message at `trace`, a failure at `debug`
+        #[cfg(feature = \"log\")]
+        fn log_parsed<T: MessageBase>(message_type: i64, len: usize, result: &Result<T, Error>) {{
+            let name = message_name(message_type).unwrap_or(\"Unknown\");
+            match result {{
+                Ok(msg) => log::trace!(\"Parsed {{}} of plugin {{}} from {{}} bytes\", name, msg.plugin_id(), len),
+                Err(e) => log::debug!(\"Could not parse {{}} from {{}} bytes: {{}}\", name, len, e),
             }}
         }}
 
+        #[cfg(not(feature = \"log\"))]
+        fn log_parsed<T>(_message_type: i64, _len: usize, _result: &Result<T, Error>) {{}}
+
+        /// Reports a failure before the message type is known, `len` is the length of the input
+        fn parse_error(e: Error, _len: usize) -> Error {{
+            #[cfg(feature = \"tracing\")]
+            tracing::warn!(error = %e, \"Could not parse message\");
+            #[cfg(feature = \"log\")]
+            log::debug!(\"Could not parse message from {{}} bytes: {{}}\", _len, e);
+            e
+        }}
+
+        #[cfg(not(feature = \"raw-input-errors\"))]
+        fn raw_input_error(e: Error, _s: &str) -> Error {{
+            e
+        }}
+
+        #[cfg(feature = \"raw-input-errors\")]
+        fn raw_input_error(e: Error, s: &str) -> Error {{
+            e.with_raw(s)
+        }}
+
+        #[cfg(not(feature = \"path-to-error\"))]
+        fn deserialize_json<'de, T, D>(deserializer: D) -> Result<T, Error>
+        where
+            T: Deserialize<'de>,
+            D: Deserializer<'de>,
+            D::Error: Display,
+        {{
+            T::deserialize(deserializer).map_err(|e|
+                Error::new(format!(\"Invalid JSON: {{}}\", e))
+            )
+        }}
+
+        #[cfg(feature = \"path-to-error\")]
+        fn deserialize_json<'de, T, D>(deserializer: D) -> Result<T, Error>
+        where
+            T: Deserialize<'de>,
+            D: Deserializer<'de>,
+            D::Error: Display,
+        {{
+            serde_path_to_error::deserialize(deserializer).map_err(|e|
+                Error::new(format!(\"Invalid JSON at {{}}: {{}}\", e.path(), e.inner()))
+            )
+        }}
+
+        impl FromStr for Message {{
+            type Err = Error;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {{
+                Self::parse(s)
+            }}
+        }}
+
+        /// Forwards to the variant, so the given serializer and its formatting are used as is
         impl ser::Serialize for Message {{
             fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
             where
                 S: Serializer,
             {{
-                match self {{
+                let result = match self {{
                     {message_serialize}
+                    Message::Unknown {{ raw, .. }} => raw.serialize(serializer),
+                }};
+                #[cfg(feature = \"log\")]
+                match &result {{
+                    Ok(_) if log::log_enabled!(log::Level::Trace) => match self.json_len() {{
+                        Ok(len) => log::trace!(\"Serialized {{}} to {{}} bytes of JSON\", self.name(), len),
+                        Err(e) => log::debug!(\"Could not serialize {{}}: {{}}\", self.name(), e),
+                    }},
+                    Ok(_) => {{}}
+                    Err(e) => log::debug!(\"Could not serialize {{}}: {{}}\", self.name(), e),
                 }}
+                result
+            }}
+        }}
+
+        impl Message {{
+            /// Length of the JSON encoding, only computed if serialization is logged at `trace`
+            #[cfg(feature = \"log\")]
+            fn json_len(&self) -> Result<usize, serde_json::Error> {{
+                match self {{
+                    {message_json_len}
+                    Message::Unknown {{ raw, .. }} => json_len(raw),
+                }}
+            }}
+        }}
+
+        /// The length of the JSON encoding of the value, without keeping the JSON in memory
+        ///
+        /// Fails like `serde_json::to_writer` if the value cannot be serialized,
+        /// e.g. a map with keys other than strings and numbers.
+        pub fn json_len<T: Serialize + ?Sized>(value: &T) -> Result<usize, serde_json::Error> {{
+            let mut counter = ByteCounter(0);
+            serde_json::to_writer(&mut counter, value)?;
+            Ok(counter.0)
+        }}
+
+        /// Discards everything written to it, only counting the bytes
+        struct ByteCounter(usize);
+
+        impl std::io::Write for ByteCounter {{
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {{
+                self.0 += buf.len();
+                Ok(buf.len())
+            }}
+
+            fn flush(&mut self) -> std::io::Result<()> {{
+                Ok(())
             }}
         }}
         ",
-        message_enum = iterate!("{name}({name}),", schemas),
+        message_enum = generate_message_enum(schemas),
         message_plugin_id = iterate!("Message::{name}(msg) => msg.plugin_id(),", schemas),
         message_message_id = iterate!("Message::{name}(_) => {name}::MESSAGE_ID,", schemas),
+        message_schema = generate_schema_for_id(schemas),
+        message_derived_schema = iterate!("(\"{name}\", schemars::schema_for!({name})),", schemas),
+        message_requires_response = generate_requires_response(schemas),
+        message_name = iterate!("Message::{name}(_) => \"{name}\",", schemas),
+        message_set_plugin_id = iterate!(
+            "Message::{name}(msg) => msg.data.plugin_id = plugin_id.to_owned(),",
+            schemas
+        ),
         message_serialize = iterate!("Message::{name}(msg) => msg.serialize(serializer),", schemas),
+        message_json_len = iterate!("Message::{name}(msg) => json_len(msg),", schemas),
         message_from_str = iterate!(
             "
-            {name}::MESSAGE_ID => 
-                Ok(Message::{name}(
-                    serde_json::from_str(s).map_err(|e| 
-                        Error {{ message: format!(\"Invalid JSON: {{}}\", e.to_string()).to_owned() }}
-                    )?
-                )),
+            {name}::MESSAGE_ID => Ok(Message::{name}(Self::decode(s, value)?)),
             ",
             schemas
         ),
+        types_path = types_path,
+        discriminator = discriminator,
+        message_type_count = schemas.len(),
+        message_ids = iterate!("{name}::MESSAGE_ID,", schemas),
+        message_schema_files = generate_schema_files(schemas),
+        schema_repository_files = generate_schema_repository_files(path),
+        message_debug = generate_debug(schemas),
+        message_correlation_id = generate_correlation_id(schemas),
+        message_set_correlation_id = generate_set_correlation_id(schemas),
+        message_property = generate_property(schemas),
+        message_rewrite_ids = generate_rewrite_ids(schemas),
+        message_arbitrary = iterate!(
+            "{name}::MESSAGE_ID => <{name} as arbitrary::Arbitrary>::arbitrary(u)?.into(),",
+            schemas
+        ),
+        message_device_id = generate_id_accessor(schemas, "deviceId"),
+        message_adapter_id = generate_id_accessor(schemas, "adapterId"),
+        message_conversions = generate_message_conversions(schemas),
+        incoming = generate_incoming(schemas),
+        plugin_error = generate_plugin_error(schemas),
+        for_plugin = generate_for_plugin(schemas, types),
+        respond = generate_respond(schemas),
         schemafy_impl = iterate!(
             "
             impl MessageType for {name} {{
@@ -228,9 +1190,45 @@ fn generate_extras(schemas: &Vec<MessageSchema>) -> String {
                     }}
                 }}
             }}
-            impl Into<Message> for {name} {{
-                fn into(self) -> Message {{
-                    Message::{name}(self)
+            impl TryFrom<&[u8]> for {name} {{
+                type Error = Error;
+
+                fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {{
+                    traced({name}::MESSAGE_ID, bytes.len(), || {{
+                        let mut deserializer = serde_json::Deserializer::from_slice(bytes);
+                        let msg: {name} = deserialize_json(&mut deserializer)?;
+                        deserializer.end().map_err(|e|
+                            Error::new(format!(\"Invalid JSON: {{}}\", e))
+                        )?;
+                        if msg.message_type != {name}::MESSAGE_ID {{
+                            return Err(Error::new(format!(
+                                \"Expected message type {{}} but got {{}}\",
+                                {name}::MESSAGE_ID, msg.message_type
+                            )));
+                        }}
+                        Ok(msg)
+                    }})
+                }}
+            }}
+            #[cfg(feature = \"arbitrary\")]
+            impl<'a> arbitrary::Arbitrary<'a> for {name} {{
+                fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {{
+                    Ok({name} {{
+                        data: u.arbitrary()?,
+                        message_type: {name}::MESSAGE_ID,
+                    }})
+                }}
+            }}
+            impl Display for {name} {{
+                fn fmt(&self, f: &mut Formatter) -> fmt::Result {{
+                    f.write_str(&serde_json::to_string(self).map_err(|_| fmt::Error)?)
+                }}
+            }}
+            impl FromStr for {name} {{
+                type Err = Error;
+
+                fn from_str(s: &str) -> Result<Self, Self::Err> {{
+                    Self::try_from(s.as_bytes())
                 }}
             }}
             impl Into<Message> for {name}MessageData {{
@@ -244,3 +1242,571 @@ fn generate_extras(schemas: &Vec<MessageSchema>) -> String {
         ),
     )
 }
+
+fn generate_schema_files(schemas: &[MessageSchema]) -> String {
+    let mut code = "".to_owned();
+
+    for schema in schemas {
+        code += &format!("({:?}, {:?}),", schema.name(), schema.file);
+    }
+
+    code
+}
+
+fn generate_schema_repository_files(path: &Path) -> String {
+    let schema_dir = path.parent().expect("Schema has parent");
+    let mut code = "".to_owned();
+
+    for file in crate::schema_files(schema_dir) {
+        let json = fs::read_to_string(&file)
+            .unwrap_or_else(|e| panic!("Read schema file {}: {}", file.display(), e));
+        let relative = file
+            .strip_prefix(schema_dir)
+            .expect("Schema file is in schema dir");
+        code += &format!("({:?}, {:?}),", relative.to_string_lossy(), json);
+    }
+
+    code
+}
+
+fn generate_schema_for_id(schemas: &[MessageSchema]) -> String {
+    let mut code = "".to_owned();
+
+    for schema in schemas {
+        let json = fs::read_to_string(&schema.path)
+            .unwrap_or_else(|e| panic!("Read schema file {}: {}", schema.path.display(), e));
+        code += &format!("{}::MESSAGE_ID => Some({:?}),", schema.name(), json);
+    }
+
+    code
+}
+
+fn generate_incoming(schemas: &[MessageSchema]) -> String {
+    let mut code = "".to_owned();
+
+    for (name, to_plugin, doc) in &[
+        (
+            "IncomingToPlugin",
+            true,
+            "The messages a plugin can receive from the gateway",
+        ),
+        (
+            "IncomingToGateway",
+            false,
+            "The messages the gateway can receive from a plugin",
+        ),
+    ] {
+        let schemas: Vec<&MessageSchema> = schemas
+            .iter()
+            .filter(|schema| schema.to_plugin() == *to_plugin)
+            .collect();
+        let mut variants = "".to_owned();
+        let mut try_from = "".to_owned();
+        let mut from = "".to_owned();
+        for schema in schemas {
+            let variant = schema.name();
+            variants += &if schema.boxed() {
+                format!("{0}(Box<{0}>),", variant)
+            } else {
+                format!("{0}({0}),", variant)
+            };
+            try_from += &format!(
+                "Message::{variant}(msg) => Ok({name}::{variant}(msg)),",
+                name = name,
+                variant = variant
+            );
+            from += &format!(
+                "{name}::{variant}(msg) => Message::{variant}(msg),",
+                name = name,
+                variant = variant
+            );
+        }
+
+        code += &format!(
+            "
+            /// {doc}
+            #[derive(Debug)]
+            pub enum {name} {{
+                {variants}
+            }}
+
+            /// Returns the message unchanged if it cannot be received on this side
+            impl TryFrom<Message> for {name} {{
+                type Error = Message;
+
+                fn try_from(msg: Message) -> Result<Self, Self::Error> {{
+                    match msg {{
+                        {try_from}
+                        msg => Err(msg),
+                    }}
+                }}
+            }}
+
+            impl From<{name}> for Message {{
+                fn from(msg: {name}) -> Self {{
+                    match msg {{
+                        {from}
+                    }}
+                }}
+            }}
+            ",
+            doc = doc,
+            name = name,
+            variants = variants,
+            try_from = try_from,
+            from = from,
+        );
+    }
+
+    let mut directions = "".to_owned();
+    for schema in schemas {
+        directions += &format!(
+            "Message::{}(_) => Some(Direction::{}),",
+            schema.name(),
+            if schema.to_plugin() { "ToPlugin" } else { "ToGateway" }
+        );
+    }
+    code += &format!(
+        "
+        /// The side a message is sent to, matching `IncomingToPlugin` and `IncomingToGateway`
+        #[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize)]
+        pub enum Direction {{
+            ToPlugin,
+            ToGateway,
+        }}
+
+        impl Message {{
+            /// The side the message is sent to, `None` for messages of unknown types
+            pub fn direction(&self) -> Option<Direction> {{
+                match self {{
+                    {directions}
+                    Message::Unknown {{ .. }} => None,
+                }}
+            }}
+        }}
+        ",
+        directions = directions,
+    );
+
+    code
+}
+
+fn generate_message_enum(schemas: &[MessageSchema]) -> String {
+    let mut code = "".to_owned();
+
+    for schema in schemas {
+        code += &if schema.boxed() {
+            format!("{0}(Box<{0}>),", schema.name())
+        } else {
+            format!("{0}({0}),", schema.name())
+        };
+    }
+
+    code
+}
+
+fn generate_message_conversions(schemas: &[MessageSchema]) -> String {
+    let mut code = "".to_owned();
+
+    for schema in schemas {
+        let (wrap, unwrap) = match schema.boxed() {
+            true => ("Box::new(self)", "*msg"),
+            false => ("self", "msg"),
+        };
+        code += &format!(
+            "
+            impl Into<Message> for {name} {{
+                fn into(self) -> Message {{
+                    Message::{name}({wrap})
+                }}
+            }}
+            impl {name} {{
+                /// Clones the message into the enum for APIs taking `&Message`
+                ///
+                /// A `{name}` is not stored inside a `Message`, so there is no borrowed view of it.
+                /// Pass the owned message with `into()` instead where possible.
+                pub fn to_message(&self) -> Message {{
+                    self.clone().into()
+                }}
+            }}
+            /// Returns the message unchanged if it is of a different type
+            impl TryFrom<Message> for {name} {{
+                type Error = Message;
+
+                fn try_from(msg: Message) -> Result<Self, Self::Error> {{
+                    match msg {{
+                        Message::{name}(msg) => Ok({unwrap}),
+                        msg => Err(msg),
+                    }}
+                }}
+            }}
+            ",
+            name = schema.name(),
+            wrap = wrap,
+            unwrap = unwrap,
+        );
+    }
+
+    code
+}
+
+fn generate_plugin_error(schemas: &[MessageSchema]) -> String {
+    let schema = schemas.iter().find(|schema| {
+        let properties = schema.data_properties();
+        schema.name().ends_with("ErrorNotification")
+            && properties.contains(&"pluginId".to_owned())
+            && properties.contains(&"message".to_owned())
+    });
+
+    match schema {
+        Some(schema) => {
+            let mut fields = "".to_owned();
+            for property in schema.data_properties() {
+                fields += &match property.as_str() {
+                    "pluginId" => "plugin_id: plugin_id.to_owned(),".to_owned(),
+                    "message" => "message: message.to_owned(),".to_owned(),
+                    _ if !schema.is_required(&property) => {
+                        format!("{}: None,", field_name(&property))
+                    }
+                    _ => return "".to_owned(),
+                };
+            }
+            format!(
+                "
+                impl Message {{
+                    #[must_use]
+                    pub fn plugin_error(plugin_id: &str, message: &str) -> Message {{
+                        {name}MessageData {{
+                            {fields}
+                        }}
+                        .into()
+                    }}
+                }}
+                ",
+                name = schema.name(),
+                fields = fields,
+            )
+        }
+        None => "".to_owned(),
+    }
+}
+
+fn generate_for_plugin(schemas: &[MessageSchema], types: &str) -> String {
+    let mut code = "".to_owned();
+
+    'schemas: for schema in schemas {
+        let data_src = format!("{}/properties/data", schema.path.display());
+        let mut properties = schema.data_properties();
+        properties.sort_by_key(|property| property != "pluginId");
+
+        let mut params = "".to_owned();
+        let mut fields = "".to_owned();
+        for property in properties {
+            let field = field_name(&property);
+            if !schema.is_required(&property) {
+                fields += &format!("{}: None,", field);
+                continue;
+            }
+            match field_type(types, &data_src, &property) {
+                Some(type_) if type_ == "String" => {
+                    params += &format!("{}: &str,", field);
+                    fields += &format!("{0}: {0}.to_owned(),", field);
+                }
+                Some(type_) => {
+                    // The types module imports Value privately
+                    let type_: Vec<&str> = type_
+                        .split(' ')
+                        .map(|token| match token {
+                            "Value" => "serde_json::Value",
+                            token => token,
+                        })
+                        .collect();
+                    params += &format!("{}: {},", field, type_.join(" "));
+                    fields += &format!("{},", field);
+                }
+                None => continue 'schemas,
+            }
+        }
+
+        code += &format!(
+            "
+            impl {name} {{
+                #[must_use]
+                #[allow(clippy::too_many_arguments)]
+                pub fn for_plugin({params}) -> {name} {{
+                    {name}MessageData {{
+                        {fields}
+                    }}
+                    .into()
+                }}
+            }}
+            ",
+            name = schema.name(),
+            params = params,
+            fields = fields,
+        );
+    }
+
+    code
+}
+
+/// The response to a request follows the naming of the protocol, e.g. `PluginUnloadRequest` and `PluginUnloadResponse`
+fn response_of<'a>(
+    request: &MessageSchema,
+    schemas: &'a [MessageSchema],
+) -> Option<&'a MessageSchema> {
+    let name = request.name();
+    let base = name.strip_suffix("Request")?;
+    schemas
+        .iter()
+        .find(|schema| schema.name() == format!("{}Response", base))
+}
+
+fn generate_requires_response(schemas: &[MessageSchema]) -> String {
+    let mut code = "".to_owned();
+
+    for schema in schemas {
+        code += &format!(
+            "Message::{}(_) => {},",
+            schema.name(),
+            response_of(schema, schemas).is_some()
+        );
+    }
+
+    code
+}
+
+fn generate_respond(schemas: &[MessageSchema]) -> String {
+    let mut code = "".to_owned();
+
+    for request in schemas {
+        let name = request.name();
+        let response = match response_of(request, schemas) {
+            Some(response) => response,
+            None => continue,
+        };
+
+        let response_properties = response.data_properties();
+        let mut fields = "".to_owned();
+        for property in request.data_properties() {
+            if !(property.ends_with("Id") && response_properties.contains(&property)) {
+                continue;
+            }
+            let field = field_name(&property);
+            let value = match request.property_type(&property) {
+                Some("integer") | Some("number") | Some("boolean") => {
+                    format!("self.data.{}", field)
+                }
+                _ => format!("self.data.{}.clone()", field),
+            };
+            fields += &match (
+                request.is_required(&property),
+                response.is_required(&property),
+            ) {
+                (true, false) => format!("data.{} = Some({});", field, value),
+                (false, true) => continue,
+                _ => format!("data.{} = {};", field, value),
+            };
+        }
+
+        code += &format!(
+            "
+            impl {name} {{
+                #[must_use]
+                pub fn respond(&self, {mutability}data: {response}MessageData) -> {response} {{
+                    {fields}
+                    data.into()
+                }}
+            }}
+            ",
+            name = name,
+            response = response.name(),
+            mutability = if fields.is_empty() { "" } else { "mut " },
+            fields = fields,
+        );
+    }
+
+    code
+}
+
+fn generate_debug(schemas: &[MessageSchema]) -> String {
+    let mut code = "".to_owned();
+
+    for schema in schemas {
+        let mut properties = schema.data_properties();
+        properties.sort_by_key(|property| property != "pluginId");
+
+        let mut fields = "".to_owned();
+        for property in properties {
+            fields += &format!(".field(\"{0}\", &msg.data.{0})", field_name(&property));
+        }
+
+        code += &format!(
+            "Message::{name}(msg) => f.debug_struct(\"Message::{name}\"){fields}.finish(),",
+            name = schema.name(),
+            fields = fields,
+        );
+    }
+
+    code
+}
+
+const CORRELATION_ID: &str = "messageId";
+
+fn generate_correlation_id(schemas: &[MessageSchema]) -> String {
+    let mut code = "".to_owned();
+
+    for schema in schemas {
+        let name = schema.name();
+        code += &if !schema.has_property(CORRELATION_ID) {
+            format!("Message::{}(_) => None,", name)
+        } else if schema.is_required(CORRELATION_ID) {
+            format!("Message::{}(msg) => Some(msg.data.message_id),", name)
+        } else {
+            format!("Message::{}(msg) => msg.data.message_id,", name)
+        };
+    }
+
+    code
+}
+
+fn generate_set_correlation_id(schemas: &[MessageSchema]) -> String {
+    let mut code = "".to_owned();
+
+    for schema in schemas {
+        let name = schema.name();
+        code += &if !schema.has_property(CORRELATION_ID) {
+            format!("Message::{}(_) => {{}}", name)
+        } else if schema.is_required(CORRELATION_ID) {
+            format!("Message::{}(msg) => msg.data.message_id = id,", name)
+        } else {
+            format!("Message::{}(msg) => msg.data.message_id = Some(id),", name)
+        };
+    }
+
+    code
+}
+
+fn generate_id_accessor(schemas: &[MessageSchema], property: &str) -> String {
+    let mut code = "".to_owned();
+
+    for schema in schemas {
+        let name = schema.name();
+        code += &if schema.property_type(property) != Some("string") {
+            format!("Message::{}(_) => None,", name)
+        } else if schema.is_required(property) {
+            format!(
+                "Message::{}(msg) => Some(&msg.data.{}),",
+                name,
+                field_name(property)
+            )
+        } else {
+            format!(
+                "Message::{}(msg) => msg.data.{}.as_deref(),",
+                name,
+                field_name(property)
+            )
+        };
+    }
+
+    code
+}
+
+fn generate_property(schemas: &[MessageSchema]) -> String {
+    let mut code = "".to_owned();
+
+    for schema in schemas {
+        let name = schema.name();
+        let data = Value::Object(schema.data().clone());
+        let property = data.pointer("/properties/property");
+        code += &if let Some(pair) =
+            property_pair(&data, "msg.data", "propertyName", "propertyValue")
+        {
+            format!("Message::{}(msg) => {},", name, pair)
+        } else if let Some(pair) =
+            property.and_then(|property| property_pair(property, "property", "name", "value"))
+        {
+            let property = match schema.is_required("property") {
+                true => "Some(&msg.data.property)",
+                false => "msg.data.property.as_ref()",
+            };
+            format!(
+                "Message::{}(msg) => {}.and_then(|property| {}),",
+                name, property, pair
+            )
+        } else {
+            format!("Message::{}(_) => None,", name)
+        };
+    }
+
+    code
+}
+
+/// Expression pairing the name and value fields of an object schema, if it has both
+fn property_pair(object: &Value, path: &str, name: &str, value: &str) -> Option<String> {
+    let properties = object.get("properties")?;
+    if properties.get(name)?.get("type").and_then(Value::as_str) != Some("string") {
+        return None;
+    }
+    if properties.get(value)?.get("type").is_some() {
+        return None;
+    }
+
+    let required = |property: &str| match object.get("required").and_then(Value::as_array) {
+        Some(required) => required.iter().any(|name| name.as_str() == Some(property)),
+        None => false,
+    };
+    let name_expr = match required(name) {
+        true => format!("Some({}.{}.as_str())", path, field_name(name)),
+        false => format!("{}.{}.as_deref()", path, field_name(name)),
+    };
+    let value_expr = match required(value) {
+        true => format!("Some(&{}.{})", path, field_name(value)),
+        false => format!("{}.{}.as_ref()", path, field_name(value)),
+    };
+
+    Some(format!("{}.zip({})", name_expr, value_expr))
+}
+
+fn generate_rewrite_ids(schemas: &[MessageSchema]) -> String {
+    let mut code = "".to_owned();
+
+    for schema in schemas {
+        let mut rewrites = "".to_owned();
+        for (property, kind) in &[
+            ("pluginId", "Plugin"),
+            ("adapterId", "Adapter"),
+            ("deviceId", "Device"),
+        ] {
+            if schema.property_type(property) != Some("string") {
+                continue;
+            }
+            let field = field_name(property);
+            rewrites += &if schema.is_required(property) {
+                format!(
+                    "if let Some(id) = f(IdKind::{kind}, &msg.data.{field}) {{
+                        msg.data.{field} = id;
+                    }}",
+                    kind = kind,
+                    field = field,
+                )
+            } else {
+                format!(
+                    "if let Some(id) = msg.data.{field}.as_deref().and_then(|id| f(IdKind::{kind}, id)) {{
+                        msg.data.{field} = Some(id);
+                    }}",
+                    kind = kind,
+                    field = field,
+                )
+            };
+        }
+
+        code += &if rewrites.is_empty() {
+            format!("Message::{}(_) => {{}}", schema.name())
+        } else {
+            format!("Message::{}(msg) => {{ {} }}", schema.name(), rewrites)
+        };
+    }
+
+    code
+}
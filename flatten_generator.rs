@@ -0,0 +1,81 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+use std::{fs::File, path::PathBuf};
+
+use serde_json::Value;
+
+/// Moves the fields of messages which are defined at the top level of their schema
+/// into a `#[serde(flatten)]` data struct.
+/// Every message then has a `data` field, while the wire format keeps the fields at the top level.
+pub fn apply(code: String, schema_files: &[PathBuf], discriminator: &str) -> String {
+    let mut code = code;
+
+    for file in schema_files {
+        let schema: Value = serde_json::from_reader(
+            File::open(file)
+                .unwrap_or_else(|e| panic!("Open schema file {}: {}", file.display(), e)),
+        )
+        .unwrap_or_else(|e| panic!("Parse JSON schema {}: {}", file.display(), e));
+
+        if is_top_level_message(&schema, discriminator) {
+            flatten(&mut code, &file.display().to_string(), discriminator);
+        }
+    }
+
+    code
+}
+
+/// A message schema with a fixed message type but without a `data` object
+fn is_top_level_message(schema: &Value, discriminator: &str) -> bool {
+    schema
+        .pointer(&format!("/properties/{}/const", discriminator))
+        .is_some()
+        && schema.pointer("/properties/data").is_none()
+}
+
+fn flatten(code: &mut String, src: &str, discriminator: &str) {
+    let message_type_field = format!(
+        "# [serde (rename = {:?})] pub message_type : i64",
+        discriminator
+    );
+    let doc = format!("# [doc = \"Generated from {}\"]", src);
+    let doc_start = match code.find(&doc) {
+        Some(doc_start) => doc_start,
+        None => return,
+    };
+    let struct_start = doc_start
+        + code[doc_start..]
+            .find("pub struct ")
+            .expect("Doc has struct");
+    let name_start = struct_start + "pub struct ".len();
+    let body_start = name_start + code[name_start..].find('{').expect("Struct has body");
+    let body_end = body_start + code[body_start..].find('}').expect("Struct body is closed");
+    let name = code[name_start..body_start].trim().to_owned();
+    let attributes = code[doc_start + doc.len()..struct_start].to_owned();
+
+    let body = &code[body_start + 1..body_end];
+    let fields = body
+        .replacen(&format!("{} ,", message_type_field), "", 1)
+        .replacen(&message_type_field, "", 1);
+
+    let message = format!(
+        "{doc}{attributes}pub struct {name} {{ \
+         # [serde (flatten)] pub data : {name}MessageData , {message_type} }}",
+        doc = doc,
+        attributes = attributes,
+        name = name,
+        message_type = message_type_field,
+    );
+    let data = format!(
+        "# [doc = \"Generated from {src}/properties/data\"]{attributes}pub struct {name}MessageData {{ {fields} }}",
+        src = src,
+        attributes = attributes,
+        name = name,
+        fields = fields,
+    );
+
+    code.replace_range(doc_start..body_end + 1, &(message + &data));
+}
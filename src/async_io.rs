@@ -0,0 +1,315 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+use std::{
+    convert::TryFrom,
+    future::Future,
+    str::{self, FromStr},
+    time::Duration,
+};
+
+use tokio::{
+    io::{self, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader},
+    sync::mpsc::{self, Receiver, Sender},
+    task::JoinHandle,
+    time::{self, Instant, MissedTickBehavior},
+};
+
+use crate::{
+    Error, Message, MessageBase, PluginRegisterRequestMessageData, PluginRegisterResponse,
+    PluginUnloadResponseMessageData, DEFAULT_MAX_FRAME_LEN,
+};
+
+/// The number of messages [`run_plugin`] reads ahead of the handler
+const RUN_PLUGIN_CHANNEL_CAPACITY: usize = 16;
+
+/// Handles the messages of the gateway in [`run_plugin`]
+pub trait AsyncMessageHandler {
+    /// Handles a message of the gateway, the returned messages are sent back to the gateway
+    ///
+    /// An error ends [`run_plugin`] with the error.
+    fn handle_message(
+        &mut self,
+        message: Message,
+    ) -> impl Future<Output = Result<Vec<Message>, Error>> + Send;
+
+    /// Handles a frame which could not be read or parsed
+    ///
+    /// Reading continues after a frame which does not parse, unless this returns an error.
+    /// After a read error the stream is closed, so [`run_plugin`] returns.
+    fn handle_frame_error(&mut self, error: Error) -> Result<(), Error> {
+        let _ = error;
+        Ok(())
+    }
+}
+
+/// Reads newline-delimited messages on a new task and sends them into the channel
+///
+/// The next frame is only read once the previous message was sent,
+/// so reading pauses while the channel is full instead of buffering.
+/// A message which cannot be parsed is sent as error and reading continues,
+/// a read error or a frame exceeding [`DEFAULT_MAX_FRAME_LEN`] is sent as error and ends the task.
+/// The task also ends at the end of the stream or when the receiver is dropped.
+pub fn spawn_reader<R>(r: R, tx: Sender<Result<Message, Error>>) -> JoinHandle<()>
+where
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    spawn_reader_with(r, tx, Message::from_str)
+}
+
+/// Like [`spawn_reader`], parsing each frame with `parse`
+fn spawn_reader_with<R>(
+    r: R,
+    tx: Sender<Result<Message, Error>>,
+    parse: fn(&str) -> Result<Message, Error>,
+) -> JoinHandle<()>
+where
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut reader = BufReader::new(r);
+        let mut line = Vec::new();
+
+        loop {
+            let (result, last) = match read_frame(&mut reader, &mut line).await {
+                Ok(0) => return,
+                Ok(_) => (parse_frame(&line, parse), false),
+                Err(e) => (Err(e), true),
+            };
+            if tx.send(result).await.is_err() || last {
+                return;
+            }
+        }
+    })
+}
+
+async fn read_frame<R: AsyncRead + Unpin>(
+    reader: &mut BufReader<R>,
+    line: &mut Vec<u8>,
+) -> Result<usize, Error> {
+    line.clear();
+    let len = reader
+        .take(DEFAULT_MAX_FRAME_LEN as u64 + 1)
+        .read_until(b'\n', line)
+        .await
+        .map_err(|e| Error::with_source(format!("Could not read message: {}", e), e))?;
+
+    if len > DEFAULT_MAX_FRAME_LEN {
+        return Err(Error::new(format!(
+            "Frame exceeds the maximum length of {} bytes",
+            DEFAULT_MAX_FRAME_LEN
+        )));
+    }
+    if len > 0 && !line.ends_with(b"\n") {
+        return Err(Error::new(format!(
+            "Stream ended within a frame after {} bytes",
+            len
+        )));
+    }
+
+    Ok(len)
+}
+
+fn parse_frame(line: &[u8], parse: fn(&str) -> Result<Message, Error>) -> Result<Message, Error> {
+    let line = str::from_utf8(line).map_err(|e| Error::new(format!("Invalid UTF-8: {}", e)))?;
+    parse(line.trim_end_matches(&['\r', '\n'][..]))
+}
+
+/// Registers the plugin and dispatches every message of the gateway to the handler
+///
+/// This is the lifecycle of `PluginConnection` on a `tokio` stream:
+/// the register request is sent and the register response awaited first.
+/// Every following message goes through [`AsyncMessageHandler::handle_message`],
+/// its responses are written back. The loop ends when the gateway closes the stream,
+/// or with the unload response once an unload request was handled.
+///
+/// A [`Message::ping`] of the gateway is answered with a [`Message::pong`],
+/// other messages of unknown types go to [`AsyncMessageHandler::handle_frame_error`].
+pub async fn run_plugin<H, S>(stream: S, plugin_id: &str, handler: H) -> Result<(), Error>
+where
+    H: AsyncMessageHandler,
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    run(stream, plugin_id, handler, None).await
+}
+
+/// How [`run_plugin_with_heartbeat`] checks that the gateway is alive
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Heartbeat {
+    /// The time between two pings
+    pub interval: Duration,
+    /// The time the gateway has to answer a ping
+    pub timeout: Duration,
+}
+
+/// Like [`run_plugin`], but pings the gateway and fails if it stops answering
+///
+/// Once registered, a [`Message::ping`] is sent at every `interval`.
+/// If no [`Message::pong`] arrives within `timeout` of the oldest unanswered ping,
+/// the gateway is considered dead and this fails.
+/// The gateway has to know the reserved message types, see [`PING_MESSAGE_TYPE`](crate::PING_MESSAGE_TYPE).
+pub async fn run_plugin_with_heartbeat<H, S>(
+    stream: S,
+    plugin_id: &str,
+    handler: H,
+    heartbeat: Heartbeat,
+) -> Result<(), Error>
+where
+    H: AsyncMessageHandler,
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    run(stream, plugin_id, handler, Some(heartbeat)).await
+}
+
+async fn run<H, S>(
+    stream: S,
+    plugin_id: &str,
+    mut handler: H,
+    heartbeat: Option<Heartbeat>,
+) -> Result<(), Error>
+where
+    H: AsyncMessageHandler,
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let (reader, mut writer) = io::split(stream);
+    let (tx, mut rx) = mpsc::channel(RUN_PLUGIN_CHANNEL_CAPACITY);
+    spawn_reader_with(reader, tx, Message::from_str_or_unknown);
+
+    let request: Message = PluginRegisterRequestMessageData {
+        plugin_id: plugin_id.to_owned(),
+    }
+    .into();
+    write_frame(&mut writer, &request).await?;
+    register_response(&mut rx, &mut handler).await?;
+
+    let mut next_ping = heartbeat.map(|heartbeat| Instant::now() + heartbeat.interval);
+    let mut pong_deadline: Option<Instant> = None;
+
+    loop {
+        let received = match next_ping.into_iter().chain(pong_deadline).min() {
+            Some(deadline) => match time::timeout_at(deadline, rx.recv()).await {
+                Ok(received) => received,
+                Err(_) => {
+                    let heartbeat = heartbeat.expect("Deadlines are only set with a heartbeat");
+                    let now = Instant::now();
+                    if pong_deadline.is_some_and(|deadline| deadline <= now) {
+                        return Err(Error::new(format!(
+                            "No pong within {:?}",
+                            heartbeat.timeout
+                        )));
+                    }
+                    write_frame(&mut writer, &Message::ping()).await?;
+                    pong_deadline.get_or_insert(now + heartbeat.timeout);
+                    next_ping = Some(now + heartbeat.interval);
+                    continue;
+                }
+            },
+            None => rx.recv().await,
+        };
+        let message = match received {
+            Some(Ok(message)) => message,
+            Some(Err(e)) => {
+                handler.handle_frame_error(e)?;
+                continue;
+            }
+            None => return Ok(()),
+        };
+
+        if message.is_ping() {
+            write_frame(&mut writer, &Message::pong()).await?;
+            continue;
+        }
+        if message.is_pong() {
+            pong_deadline = None;
+            continue;
+        }
+        if let Message::Unknown { message_type, .. } = message {
+            handler.handle_frame_error(Error::unknown_message_type(message_type))?;
+            continue;
+        }
+
+        let unload = matches!(message, Message::PluginUnloadRequest(_));
+        for response in handler.handle_message(message).await? {
+            write_frame(&mut writer, &response).await?;
+        }
+        if unload {
+            let response: Message = PluginUnloadResponseMessageData {
+                plugin_id: plugin_id.to_owned(),
+            }
+            .into();
+            return write_frame(&mut writer, &response).await;
+        }
+    }
+}
+
+async fn register_response<H: AsyncMessageHandler>(
+    rx: &mut Receiver<Result<Message, Error>>,
+    handler: &mut H,
+) -> Result<PluginRegisterResponse, Error> {
+    loop {
+        match rx
+            .recv()
+            .await
+            .map(|result| result.map(PluginRegisterResponse::try_from))
+        {
+            Some(Ok(Ok(response))) => return Ok(response),
+            Some(Ok(Err(msg))) => {
+                return Err(Error::new(format!(
+                    "Expected register response but got message type {}",
+                    msg.message_id()
+                )))
+            }
+            Some(Err(e)) => handler.handle_frame_error(e)?,
+            None => {
+                return Err(Error::new(
+                    "Connection closed before register response".to_owned(),
+                ))
+            }
+        }
+    }
+}
+
+/// Writes the messages as newline-delimited frames at `per_sec` messages per second
+///
+/// The first frame is written right away. A writer slower than the rate delays the following
+/// frames instead of writing them in a burst to catch up. Fails if `per_sec` is zero.
+pub async fn replay_messages<W: AsyncWrite + Unpin>(
+    w: &mut W,
+    msgs: &[Message],
+    per_sec: u32,
+) -> Result<(), Error> {
+    if per_sec == 0 {
+        return Err(Error::new(
+            "Cannot replay at zero messages per second".to_owned(),
+        ));
+    }
+    let mut interval = time::interval(Duration::from_secs(1) / per_sec);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    for message in msgs {
+        interval.tick().await;
+        write_frame(w, message).await?;
+    }
+
+    Ok(())
+}
+
+/// Writes a message followed by a newline and flushes the writer
+async fn write_frame<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    message: &Message,
+) -> Result<(), Error> {
+    let mut line = serde_json::to_vec(message)
+        .map_err(|e| Error::new(format!("Could not serialize message: {}", e)))?;
+    line.push(b'\n');
+    writer
+        .write_all(&line)
+        .await
+        .map_err(|e| Error::with_source(format!("Could not write message: {}", e), e))?;
+    writer
+        .flush()
+        .await
+        .map_err(|e| Error::with_source(format!("Could not write message: {}", e), e))
+}
@@ -0,0 +1,66 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+use std::{
+    env,
+    io::{self, Read},
+    process,
+    str::FromStr,
+};
+
+use serde_json::Value;
+use webthings_gateway_ipc_types::{Message, MessageBase};
+
+const USAGE: &str = "Usage: webthings-ipc <parse|validate|id> < message.json
+
+    parse       Print the parsed message and its JSON
+    validate    Check that the message conforms to the schema
+    id          Print the message type and name";
+
+fn main() {
+    let command = match env::args().nth(1) {
+        Some(command) if ["parse", "validate", "id"].contains(&command.as_str()) => command,
+        _ => exit(USAGE),
+    };
+
+    let mut input = String::new();
+    if let Err(e) = io::stdin().read_to_string(&mut input) {
+        exit(&format!("Could not read stdin: {}", e));
+    }
+
+    if command == "validate" {
+        let value: Value = match serde_json::from_str(input.trim()) {
+            Ok(value) => value,
+            Err(e) => exit(&format!("Invalid JSON: {}", e)),
+        };
+        if let Err(errors) = Message::validate_value(&value) {
+            let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+            exit(&errors.join("\n"));
+        }
+    }
+
+    let message = match Message::from_str(input.trim()) {
+        Ok(message) => message,
+        Err(e) => exit(&e.to_string()),
+    };
+
+    match command.as_str() {
+        "parse" => {
+            println!("{:#?}", message);
+            match message.to_string_pretty() {
+                Ok(json) => println!("{}", json),
+                Err(e) => exit(&e.to_string()),
+            }
+        }
+        "validate" => println!("Valid {}", message.name()),
+        "id" => println!("{} {}", message.message_id(), message.name()),
+        _ => exit(USAGE),
+    }
+}
+
+fn exit(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
+}
@@ -0,0 +1,92 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{Message, MESSAGE_IDS, MESSAGE_TYPE_KEY};
+
+/// The message type of [`Message::capabilities_from_supported`]
+///
+/// The schema has no message to exchange capabilities and only uses positive message types,
+/// so the capabilities are sent as a message of this type, which peers parse as `Message::Unknown`.
+pub const CAPABILITIES_MESSAGE_TYPE: i64 = -1000;
+
+/// The message types supported by one side of a connection
+#[derive(Clone, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub struct Capabilities {
+    #[serde(rename = "messageTypes")]
+    message_types: BTreeSet<i64>,
+}
+
+impl Capabilities {
+    pub fn from_supported(ids: &[i64]) -> Self {
+        Self {
+            message_types: ids.iter().copied().collect(),
+        }
+    }
+
+    /// All message types known to this crate
+    pub fn builtin() -> Self {
+        Self::from_supported(&MESSAGE_IDS)
+    }
+
+    pub fn supports(&self, id: i64) -> bool {
+        self.message_types.contains(&id)
+    }
+
+    /// The message types supported by both sides
+    pub fn common(&self, other: &Capabilities) -> Capabilities {
+        Self {
+            message_types: self
+                .message_types
+                .intersection(&other.message_types)
+                .copied()
+                .collect(),
+        }
+    }
+
+    pub fn message_types(&self) -> impl Iterator<Item = i64> + '_ {
+        self.message_types.iter().copied()
+    }
+}
+
+impl Message {
+    /// A message advertising the supported message types, e.g. right after registering
+    ///
+    /// It is sent as `{"messageType": -1000, "data": {"messageTypes": [...]}}`,
+    /// which the receiving side has to parse with [`Message::from_str_or_unknown`].
+    pub fn capabilities_from_supported(ids: &[i64]) -> Message {
+        let capabilities = Capabilities::from_supported(ids);
+        let mut raw = Map::new();
+        raw.insert(
+            MESSAGE_TYPE_KEY.to_owned(),
+            CAPABILITIES_MESSAGE_TYPE.into(),
+        );
+        raw.insert(
+            "data".to_owned(),
+            serde_json::to_value(capabilities).expect("Capabilities are serializable"),
+        );
+
+        Message::Unknown {
+            message_type: CAPABILITIES_MESSAGE_TYPE,
+            raw: Value::Object(raw),
+        }
+    }
+
+    /// The capabilities advertised by a message of [`Message::capabilities_from_supported`]
+    pub fn capabilities(&self) -> Option<Capabilities> {
+        match self {
+            Message::Unknown { message_type, raw }
+                if *message_type == CAPABILITIES_MESSAGE_TYPE =>
+            {
+                Capabilities::deserialize(raw.get("data")?).ok()
+            }
+            _ => None,
+        }
+    }
+}
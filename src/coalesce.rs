@@ -0,0 +1,35 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+use std::collections::HashSet;
+
+use crate::Message;
+
+/// Keeps only the last property changed notification per device and property
+///
+/// All other messages are passed through, the relative order of the kept messages is unchanged.
+pub fn coalesce_property_changes(msgs: Vec<Message>) -> Vec<Message> {
+    let mut seen = HashSet::new();
+    let mut kept: Vec<Message> = msgs
+        .into_iter()
+        .rev()
+        .filter(|msg| match property_change_key(msg) {
+            Some(key) => seen.insert(key),
+            None => true,
+        })
+        .collect();
+    kept.reverse();
+    kept
+}
+
+fn property_change_key(msg: &Message) -> Option<(String, String)> {
+    if !msg.name().ends_with("PropertyChangedNotification") {
+        return None;
+    }
+
+    let device_id = msg.device_id()?;
+    let (property, _) = msg.property()?;
+    Some((device_id.to_owned(), property.to_owned()))
+}
@@ -0,0 +1,104 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+use std::convert::TryFrom;
+use std::io::{BufReader, Read, Write};
+
+use crate::{
+    io::{read_message, write_message},
+    Error, Message, MessageBase, PluginRegisterRequestMessageData, PluginRegisterResponse,
+    PluginUnloadResponseMessageData,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ConnectionState {
+    Connecting,
+    Registered,
+    Unloading,
+}
+
+/// The plugin side of a gateway connection
+///
+/// Operations are only accepted in the matching state:
+/// `register` while connecting, `next_message` and `unload` once registered.
+pub struct PluginConnection<S: Read + Write> {
+    stream: BufReader<S>,
+    plugin_id: String,
+    state: ConnectionState,
+}
+
+impl<S: Read + Write> PluginConnection<S> {
+    pub fn new(stream: S, plugin_id: &str) -> Self {
+        Self {
+            stream: BufReader::new(stream),
+            plugin_id: plugin_id.to_owned(),
+            state: ConnectionState::Connecting,
+        }
+    }
+
+    pub fn plugin_id(&self) -> &str {
+        &self.plugin_id
+    }
+
+    pub fn state(&self) -> ConnectionState {
+        self.state
+    }
+
+    /// Sends the register request and waits for the response of the gateway
+    pub fn register(&mut self) -> Result<PluginRegisterResponse, Error> {
+        self.expect_state(ConnectionState::Connecting, "register")?;
+
+        let request: Message = PluginRegisterRequestMessageData {
+            plugin_id: self.plugin_id.clone(),
+        }
+        .into();
+        write_message(self.stream.get_mut(), &request)?;
+
+        match read_message(&mut self.stream)?.map(PluginRegisterResponse::try_from) {
+            Some(Ok(response)) => {
+                self.state = ConnectionState::Registered;
+                Ok(response)
+            }
+            Some(Err(msg)) => Err(Error::new(format!(
+                "Expected register response but got message type {}",
+                msg.message_id()
+            ))),
+            None => Err(Error::new(
+                "Connection closed before register response".to_owned(),
+            )),
+        }
+    }
+
+    /// Reads the next message, returns `None` if the gateway closed the connection
+    ///
+    /// A frame exceeding [`DEFAULT_MAX_FRAME_LEN`](crate::DEFAULT_MAX_FRAME_LEN) fails.
+    pub fn next_message(&mut self) -> Result<Option<Message>, Error> {
+        self.expect_state(ConnectionState::Registered, "read messages")?;
+        read_message(&mut self.stream)
+    }
+
+    /// Sends the unload response, no further operations are accepted afterwards
+    pub fn unload(&mut self) -> Result<(), Error> {
+        self.expect_state(ConnectionState::Registered, "unload")?;
+
+        let response: Message = PluginUnloadResponseMessageData {
+            plugin_id: self.plugin_id.clone(),
+        }
+        .into();
+        self.state = ConnectionState::Unloading;
+        write_message(self.stream.get_mut(), &response)
+    }
+
+    fn expect_state(&self, expected: ConnectionState, operation: &str) -> Result<(), Error> {
+        if self.state == expected {
+            Ok(())
+        } else {
+            Err(Error::new(format!(
+                "Cannot {} in state {:?}",
+                operation, self.state
+            )))
+        }
+    }
+}
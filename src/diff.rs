@@ -0,0 +1,76 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+use std::collections::BTreeSet;
+
+use serde_json::Value;
+
+use crate::{Message, MessageBase};
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum FieldDiff {
+    /// The messages are of different types
+    TypeMismatch {
+        old: &'static str,
+        new: &'static str,
+    },
+    /// The field at the JSON pointer `path` differs, `None` means the field is absent
+    Changed {
+        path: String,
+        old: Option<Value>,
+        new: Option<Value>,
+    },
+}
+
+impl Message {
+    /// Lists the fields which differ between both messages
+    pub fn diff(&self, other: &Message) -> Vec<FieldDiff> {
+        if self.message_id() != other.message_id() {
+            return vec![FieldDiff::TypeMismatch {
+                old: self.name(),
+                new: other.name(),
+            }];
+        }
+
+        let old = self.to_value().unwrap_or(Value::Null);
+        let new = other.to_value().unwrap_or(Value::Null);
+        let mut diffs = Vec::new();
+        diff_values("", Some(&old), Some(&new), &mut diffs);
+        diffs
+    }
+
+    /// Compares both messages except for the fields at the given JSON pointers and below
+    pub fn eq_ignoring(&self, other: &Message, fields: &[&str]) -> bool {
+        self.diff(other).iter().all(|diff| match diff {
+            FieldDiff::TypeMismatch { .. } => false,
+            FieldDiff::Changed { path, .. } => fields.iter().any(|field| {
+                path == field || (path.starts_with(field) && path[field.len()..].starts_with('/'))
+            }),
+        })
+    }
+}
+
+fn diff_values(path: &str, old: Option<&Value>, new: Option<&Value>, diffs: &mut Vec<FieldDiff>) {
+    match (old, new) {
+        (Some(Value::Object(old)), Some(Value::Object(new))) => {
+            let keys: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+            for key in keys {
+                let path = format!("{}/{}", path, key.replace('~', "~0").replace('/', "~1"));
+                diff_values(&path, old.get(key), new.get(key), diffs);
+            }
+        }
+        (Some(Value::Array(old)), Some(Value::Array(new))) => {
+            for i in 0..old.len().max(new.len()) {
+                diff_values(&format!("{}/{}", path, i), old.get(i), new.get(i), diffs);
+            }
+        }
+        (old, new) if old != new => diffs.push(FieldDiff::Changed {
+            path: path.to_owned(),
+            old: old.cloned(),
+            new: new.cloned(),
+        }),
+        _ => {}
+    }
+}
@@ -0,0 +1,57 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+use std::str::FromStr;
+
+use serde_json::{Map, Value};
+
+use crate::{Error, Message, MESSAGE_TYPE_KEY};
+
+impl Message {
+    /// Serializes the message with the data fields next to the message type instead of under `data`
+    pub fn to_flat_string(&self) -> Result<String, Error> {
+        let mut value = self.to_value()?;
+
+        if let Some(Value::Object(data)) = value.as_object_mut().and_then(|map| map.remove("data"))
+        {
+            if let Some(map) = value.as_object_mut() {
+                map.extend(data);
+            }
+        }
+
+        Ok(value.to_string())
+    }
+
+    /// Parses a message serialized by [`Message::to_flat_string`]
+    ///
+    /// Messages whose schema has no `data` object are parsed as they are.
+    pub fn from_flat_str(s: &str) -> Result<Message, Error> {
+        let map: Map<String, Value> =
+            serde_json::from_str(s).map_err(|e| Error::new(format!("Invalid message: {}", e)))?;
+        let message_type = map
+            .get(MESSAGE_TYPE_KEY)
+            .and_then(Value::as_i64)
+            .ok_or_else(|| Error::new(format!("Invalid message: missing {}", MESSAGE_TYPE_KEY)))?;
+
+        // Unknown message types are left to `from_str` to report
+        let schema = Message::schema_for_id(message_type)
+            .and_then(|schema| serde_json::from_str::<Value>(schema).ok());
+        let nested = match schema {
+            Some(schema) => schema.pointer("/properties/data").is_some(),
+            None => true,
+        };
+        if !nested {
+            return Message::from_str(s);
+        }
+
+        let (envelope, data): (Map<String, Value>, Map<String, Value>) = map
+            .into_iter()
+            .partition(|(key, _)| key == MESSAGE_TYPE_KEY);
+        let mut value = Value::Object(envelope);
+        value["data"] = Value::Object(data);
+
+        Message::from_str(&value.to_string())
+    }
+}
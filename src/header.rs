@@ -0,0 +1,80 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+use std::{fmt, str::FromStr};
+
+use serde::{
+    de::{self, IgnoredAny, MapAccess, Visitor},
+    Deserialize, Deserializer,
+};
+
+use crate::{Error, MESSAGE_TYPE_KEY};
+
+/// The routing relevant fields of a message, the rest of the payload is skipped while parsing
+///
+/// The ids are read from `data` and, for messages defined at the top level of their schema,
+/// next to the message type. Messages of unknown types may have no plugin id.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct MessageHeader {
+    pub message_type: i64,
+    pub plugin_id: Option<String>,
+    pub correlation_id: Option<i64>,
+}
+
+#[derive(Deserialize, Default)]
+struct Ids {
+    #[serde(rename = "pluginId")]
+    plugin_id: Option<String>,
+    #[serde(rename = "messageId")]
+    message_id: Option<i64>,
+}
+
+impl<'de> Deserialize<'de> for MessageHeader {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(HeaderVisitor)
+    }
+}
+
+struct HeaderVisitor;
+
+impl<'de> Visitor<'de> for HeaderVisitor {
+    type Value = MessageHeader;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a message object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut message_type = None;
+        let mut data = Ids::default();
+        let mut top_level = Ids::default();
+
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                key if key == MESSAGE_TYPE_KEY => message_type = Some(map.next_value()?),
+                "data" => data = map.next_value::<Option<Ids>>()?.unwrap_or_default(),
+                "pluginId" => top_level.plugin_id = map.next_value()?,
+                "messageId" => top_level.message_id = map.next_value()?,
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+
+        Ok(MessageHeader {
+            message_type: message_type.ok_or_else(|| de::Error::missing_field(MESSAGE_TYPE_KEY))?,
+            plugin_id: data.plugin_id.or(top_level.plugin_id),
+            correlation_id: data.message_id.or(top_level.message_id),
+        })
+    }
+}
+
+impl FromStr for MessageHeader {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s).map_err(|e| Error::new(format!("Invalid message header: {}", e)))
+    }
+}
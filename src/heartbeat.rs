@@ -0,0 +1,52 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+use serde_json::{Map, Value};
+
+use crate::{Message, MESSAGE_TYPE_KEY};
+
+/// The message type of [`Message::ping`]
+///
+/// The schema has no heartbeat message, so like the capabilities, pings and pongs are sent
+/// as messages of reserved negative types, which peers parse as `Message::Unknown`.
+/// Only peers which know these types answer a ping, e.g. `run_plugin_with_heartbeat`.
+pub const PING_MESSAGE_TYPE: i64 = -1001;
+
+/// The message type of [`Message::pong`], the answer to a ping
+pub const PONG_MESSAGE_TYPE: i64 = -1002;
+
+impl Message {
+    /// A message asking the peer to answer with [`Message::pong`], to detect a dead peer
+    ///
+    /// It is sent as `{"messageType": -1001, "data": {}}`,
+    /// which the receiving side has to parse with [`Message::from_str_or_unknown`].
+    pub fn ping() -> Message {
+        heartbeat(PING_MESSAGE_TYPE)
+    }
+
+    /// The answer to [`Message::ping`], sent as `{"messageType": -1002, "data": {}}`
+    pub fn pong() -> Message {
+        heartbeat(PONG_MESSAGE_TYPE)
+    }
+
+    pub fn is_ping(&self) -> bool {
+        matches!(self, Message::Unknown { message_type, .. } if *message_type == PING_MESSAGE_TYPE)
+    }
+
+    pub fn is_pong(&self) -> bool {
+        matches!(self, Message::Unknown { message_type, .. } if *message_type == PONG_MESSAGE_TYPE)
+    }
+}
+
+fn heartbeat(message_type: i64) -> Message {
+    let mut raw = Map::new();
+    raw.insert(MESSAGE_TYPE_KEY.to_owned(), message_type.into());
+    raw.insert("data".to_owned(), Value::Object(Map::new()));
+
+    Message::Unknown {
+        message_type,
+        raw: Value::Object(raw),
+    }
+}
@@ -0,0 +1,354 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+use std::{
+    convert::TryFrom,
+    fs::File,
+    io::{BufRead, BufReader, ErrorKind, Read, Write},
+    path::Path,
+    str::{self, FromStr},
+};
+
+use serde::Serialize;
+use serde_json::{ser::PrettyFormatter, Serializer};
+
+use crate::{json_len, Error, Message};
+
+/// Reads the next newline-delimited message, returns `None` at the end of the stream
+///
+/// The last message of the stream may lack its newline.
+/// A frame exceeding [`DEFAULT_MAX_FRAME_LEN`] fails without buffering the rest of it,
+/// use [`MessageReader`] for another limit.
+pub fn read_message<R: BufRead>(reader: &mut R) -> Result<Option<Message>, Error> {
+    let mut line = Vec::new();
+
+    if read_frame(reader, &mut line, DEFAULT_MAX_FRAME_LEN)? == 0 {
+        return Ok(None);
+    }
+
+    parse_line(&line).map(Some)
+}
+
+/// Writes a message followed by a newline and flushes the writer
+pub fn write_message<W: Write>(writer: &mut W, message: &Message) -> Result<(), Error> {
+    let mut line = serde_json::to_string(message)
+        .map_err(|e| Error::new(format!("Could not serialize message: {}", e)))?;
+    line.push('\n');
+    writer
+        .write_all(line.as_bytes())
+        .and_then(|_| writer.flush())
+        .map_err(|e| Error::with_source(format!("Could not write message: {}", e), e))
+}
+
+/// Writes a message as indented JSON followed by a newline and flushes the writer
+pub fn write_pretty<W: Write>(
+    writer: &mut W,
+    message: &Message,
+    indent: &str,
+) -> Result<(), Error> {
+    let mut json = Vec::new();
+    let formatter = PrettyFormatter::with_indent(indent.as_bytes());
+    message
+        .serialize(&mut Serializer::with_formatter(&mut json, formatter))
+        .map_err(|e| Error::new(format!("Could not serialize message: {}", e)))?;
+    json.push(b'\n');
+    writer
+        .write_all(&json)
+        .and_then(|_| writer.flush())
+        .map_err(|e| Error::with_source(format!("Could not write message: {}", e), e))
+}
+
+impl Message {
+    /// Reads a capture of newline-delimited messages, blank lines are skipped
+    ///
+    /// A line exceeding [`DEFAULT_MAX_FRAME_LEN`] fails.
+    pub fn read_lines_from_file<P: AsRef<Path>>(path: P) -> Result<Vec<Message>, Error> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(|e| {
+            Error::with_source(format!("Could not open {}: {}", path.display(), e), e)
+        })?;
+        let mut reader = BufReader::new(file);
+        let mut line = Vec::new();
+        let mut messages = Vec::new();
+
+        for index in 1.. {
+            let context = format!("Line {}", index);
+            let len = read_frame(&mut reader, &mut line, DEFAULT_MAX_FRAME_LEN)
+                .map_err(|e| e.context(&context))?;
+            if len == 0 {
+                break;
+            }
+            if line.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+            messages.push(parse_line(&line).map_err(|e| e.context(&context))?);
+        }
+
+        Ok(messages)
+    }
+
+    /// Serializes the message directly into the writer, without a trailing newline
+    ///
+    /// The JSON is not buffered, so an unbuffered sink should be wrapped in a `BufWriter`.
+    /// If serialization fails, part of the message may already have been written.
+    pub fn to_writer<W: Write>(&self, writer: W) -> Result<(), Error> {
+        serde_json::to_writer(writer, self)
+            .map_err(|e| Error::new(format!("Could not serialize message: {}", e)))
+    }
+
+    /// Like [`to_writer`](Message::to_writer), but indented by two spaces
+    pub fn to_writer_pretty<W: Write>(&self, writer: W) -> Result<(), Error> {
+        serde_json::to_writer_pretty(writer, self)
+            .map_err(|e| Error::new(format!("Could not serialize message: {}", e)))
+    }
+
+    /// The number of bytes [`write_message`] writes for the message, including the newline
+    ///
+    /// The message is serialized into a counter, so the JSON is not kept in memory.
+    pub fn wire_len(&self) -> Result<usize, Error> {
+        json_len(self)
+            .map(|len| len + 1)
+            .map_err(|e| Error::new(format!("Could not serialize message: {}", e)))
+    }
+
+    /// Serializes the message prefixed by its length as big-endian `u32`, pairs with [`read_framed`]
+    pub fn to_framed_bytes(&self) -> Result<Vec<u8>, Error> {
+        let json = serde_json::to_vec(self)
+            .map_err(|e| Error::new(format!("Could not serialize message: {}", e)))?;
+        let len = u32::try_from(json.len())
+            .map_err(|_| Error::new(format!("Message too large: {} bytes", json.len())))?;
+
+        let mut bytes = Vec::with_capacity(4 + json.len());
+        bytes.extend_from_slice(&len.to_be_bytes());
+        bytes.extend_from_slice(&json);
+        Ok(bytes)
+    }
+}
+
+/// Reads the next length-prefixed message, returns `None` at the end of the stream
+///
+/// The stream may only end before a frame, ending within the length prefix fails as well.
+/// Each frame starts with the length of the JSON message as big-endian `u32`.
+/// Frames longer than [`DEFAULT_MAX_FRAME_LEN`] fail before their payload is read.
+pub fn read_framed<R: Read>(reader: &mut R) -> Result<Option<Message>, Error> {
+    read_framed_with_max(reader, DEFAULT_MAX_FRAME_LEN)
+}
+
+/// Like [`read_framed`] with another maximum length of the JSON message
+///
+/// The payload of a longer frame is left in the stream.
+pub fn read_framed_with_max<R: Read>(
+    reader: &mut R,
+    max_len: usize,
+) -> Result<Option<Message>, Error> {
+    let len = match read_prefix(reader)? {
+        Some(len) => len as usize,
+        None => return Ok(None),
+    };
+    if len > max_len {
+        return Err(Error::new(format!(
+            "Frame of {} bytes exceeds the maximum length of {} bytes",
+            len, max_len
+        )));
+    }
+
+    let mut json = vec![0; len];
+    reader
+        .read_exact(&mut json)
+        .map_err(|e| Error::with_source(format!("Could not read message: {}", e), e))?;
+    let json = str::from_utf8(&json).map_err(|e| Error::new(format!("Invalid UTF-8: {}", e)))?;
+
+    Message::from_str(json).map(Some)
+}
+
+/// Reads the big-endian `u32` length prefix, `None` if the stream ends before its first byte
+fn read_prefix<R: Read>(reader: &mut R) -> Result<Option<u32>, Error> {
+    let mut prefix = [0; 4];
+    let mut read = 0;
+
+    while read < prefix.len() {
+        match reader.read(&mut prefix[read..]) {
+            Ok(0) if read == 0 => return Ok(None),
+            Ok(0) => {
+                return Err(Error::new(format!(
+                    "Stream ended within the length prefix after {} bytes",
+                    read
+                )))
+            }
+            Ok(len) => read += len,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => {
+                return Err(Error::with_source(
+                    format!("Could not read message: {}", e),
+                    e,
+                ))
+            }
+        }
+    }
+
+    Ok(Some(u32::from_be_bytes(prefix)))
+}
+
+/// Writes all messages with a single write call and flushes the writer
+///
+/// Nothing is written if one of the messages cannot be serialized.
+pub fn write_messages<W: Write>(writer: &mut W, messages: &[Message]) -> Result<(), Error> {
+    let mut buffer = Vec::new();
+
+    for (index, message) in messages.iter().enumerate() {
+        serde_json::to_writer(&mut buffer, message)
+            .map_err(|e| Error::new(format!("Could not serialize message {}: {}", index, e)))?;
+        buffer.push(b'\n');
+    }
+
+    writer
+        .write_all(&buffer)
+        .and_then(|_| writer.flush())
+        .map_err(|e| Error::with_source(format!("Could not write messages: {}", e), e))
+}
+
+/// Frames longer than this are rejected by the readers unless configured otherwise
+pub const DEFAULT_MAX_FRAME_LEN: usize = 16 * 1024 * 1024;
+
+/// Reads newline-delimited messages one at a time, reusing its line buffer
+pub struct MessageReader<R: BufRead> {
+    reader: R,
+    line: Vec<u8>,
+    max_len: usize,
+}
+
+impl<R: BufRead> MessageReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            line: Vec::new(),
+            max_len: DEFAULT_MAX_FRAME_LEN,
+        }
+    }
+
+    /// Limits the length of a frame including its line ending, see [`DEFAULT_MAX_FRAME_LEN`]
+    #[must_use]
+    pub fn with_max_len(mut self, max_len: usize) -> Self {
+        self.max_len = max_len;
+        self
+    }
+
+    /// Returns `None` at the end of the stream, fails if the stream ends within a frame
+    ///
+    /// A frame exceeding the maximum length fails without buffering the rest of it,
+    /// which is left in the stream.
+    pub fn read(&mut self) -> Result<Option<Message>, Error> {
+        let len = read_frame(&mut self.reader, &mut self.line, self.max_len)?;
+
+        if len == 0 {
+            return Ok(None);
+        }
+        if !self.line.ends_with(b"\n") {
+            return Err(Error::new(format!(
+                "Stream ended within a frame after {} bytes",
+                len
+            )));
+        }
+
+        parse_line(&self.line).map(Some)
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+/// A frame which could not be parsed
+///
+/// `raw` holds the bytes of the frame including its line ending, even if they are not UTF-8,
+/// it is empty if the frame exceeds the maximum length or reading from the stream failed.
+#[derive(Debug)]
+pub struct RawFrame<'a> {
+    pub raw: &'a [u8],
+    pub error: Error,
+}
+
+/// Reads newline-delimited frames and keeps the raw bytes of each frame
+///
+/// The raw frame includes its line ending, so forwarding it reproduces the stream byte for byte.
+pub struct FramedReader<R: BufRead> {
+    reader: R,
+    line: Vec<u8>,
+    max_len: usize,
+    failed: bool,
+}
+
+impl<R: BufRead> FramedReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            line: Vec::new(),
+            max_len: DEFAULT_MAX_FRAME_LEN,
+            failed: false,
+        }
+    }
+
+    /// Limits the length of a frame including its line ending, see [`DEFAULT_MAX_FRAME_LEN`]
+    #[must_use]
+    pub fn with_max_len(mut self, max_len: usize) -> Self {
+        self.max_len = max_len;
+        self
+    }
+
+    /// Returns `None` at the end of the stream, after a read error or a frame exceeding the maximum length
+    pub fn next_frame(&mut self) -> Option<Result<(Message, &str), RawFrame<'_>>> {
+        if self.failed {
+            return None;
+        }
+
+        match read_frame(&mut self.reader, &mut self.line, self.max_len) {
+            Ok(0) => None,
+            Ok(_) => Some(match str::from_utf8(&self.line) {
+                Ok(raw) => Message::from_str(raw.trim_end_matches(&['\r', '\n'][..]))
+                    .map(|message| (message, raw))
+                    .map_err(|error| RawFrame {
+                        raw: raw.as_bytes(),
+                        error,
+                    }),
+                Err(e) => Err(RawFrame {
+                    raw: &self.line,
+                    error: Error::new(format!("Invalid UTF-8: {}", e)),
+                }),
+            }),
+            Err(error) => {
+                self.failed = true;
+                Some(Err(RawFrame { raw: &[], error }))
+            }
+        }
+    }
+}
+
+/// Parses a frame read by [`read_frame`] without its line ending
+fn parse_line(line: &[u8]) -> Result<Message, Error> {
+    let line = str::from_utf8(line).map_err(|e| Error::new(format!("Invalid UTF-8: {}", e)))?;
+    Message::from_str(line.trim_end_matches(&['\r', '\n'][..]))
+}
+
+/// Reads up to and including the next newline into `line`, fails if that exceeds `max_len` bytes
+fn read_frame<R: BufRead>(
+    reader: &mut R,
+    line: &mut Vec<u8>,
+    max_len: usize,
+) -> Result<usize, Error> {
+    line.clear();
+    let len = reader
+        .take(max_len as u64 + 1)
+        .read_until(b'\n', line)
+        .map_err(|e| Error::with_source(format!("Could not read message: {}", e), e))?;
+
+    if len > max_len {
+        return Err(Error::new(format!(
+            "Frame exceeds the maximum length of {} bytes",
+            max_len
+        )));
+    }
+
+    Ok(len)
+}
@@ -5,10 +5,90 @@
  */
 
 /// Automatically generated type definitions for the WebThings gateway addon IPC protocol
-mod types;
+mod types {
+    include!(concat!(env!("OUT_DIR"), "/types.rs"));
+}
 
 /// Additional Traits and implementations for the types
-mod extras;
+mod extras {
+    include!(concat!(env!("OUT_DIR"), "/extras.rs"));
+}
 
+/// Helper methods for the generated message types
+mod message;
+
+/// Structural comparison of messages
+mod diff;
+
+/// Parsing of the message envelope only
+mod header;
+
+/// Tolerant parsing of non-conformant input
+mod lossy;
+
+/// Runtime registration of custom message types
+mod registry;
+
+/// Newline-delimited message framing
+mod io;
+
+/// Plugin side connection lifecycle
+mod connection;
+
+/// Negotiation of supported message types
+mod capabilities;
+
+/// Liveness checks with ping and pong messages
+mod heartbeat;
+
+/// Deduplication of property change bursts
+mod coalesce;
+
+/// Flat log records of messages
+mod record;
+
+/// Typed access to property values
+mod property;
+
+/// Reading messages into a `tokio` channel
+#[cfg(feature = "async")]
+mod async_io;
+
+/// Protobuf envelope for forwarding messages
+#[cfg(feature = "proto")]
+mod proto;
+
+/// MessagePack encoding of messages
+#[cfg(feature = "msgpack")]
+mod msgpack;
+
+/// Wire format with the data fields at the top level
+#[cfg(feature = "compat-flat")]
+mod flat;
+
+/// Validation of messages against their schema
+#[cfg(feature = "validation")]
+mod validation;
+
+#[cfg(feature = "async")]
+pub use async_io::*;
+pub use capabilities::*;
+pub use coalesce::*;
+pub use connection::*;
+pub use diff::*;
 pub use extras::*;
+pub use header::*;
+pub use heartbeat::*;
+pub use io::*;
+pub use lossy::*;
+pub use message::*;
+pub use property::*;
+pub use record::*;
+pub use registry::*;
+#[cfg(feature = "msgpack")]
+pub use msgpack::*;
+#[cfg(feature = "proto")]
+pub use proto::*;
 pub use types::*;
+#[cfg(feature = "validation")]
+pub use validation::*;
@@ -0,0 +1,107 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+use std::str::FromStr;
+
+use serde_json::Value;
+
+use crate::{Error, Message, MESSAGE_IDS, MESSAGE_TYPE_KEY};
+
+/// Formatting noise removed by [`Message::from_str_lossy`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Normalization {
+    StrippedByteOrderMark,
+    TrimmedWhitespace,
+    RemovedTrailingCommas,
+}
+
+impl Message {
+    /// Parses a message after removing formatting noise of non-conformant senders
+    ///
+    /// Only the formatting is normalized, the message itself is validated as strict as in `from_str`.
+    pub fn from_str_lossy(s: &str) -> Result<(Message, Vec<Normalization>), Error> {
+        let mut normalizations = Vec::new();
+        let mut s = s;
+
+        if let Some(stripped) = s.strip_prefix('\u{feff}') {
+            normalizations.push(Normalization::StrippedByteOrderMark);
+            s = stripped;
+        }
+
+        let trimmed = s.trim();
+        if trimmed.len() != s.len() {
+            normalizations.push(Normalization::TrimmedWhitespace);
+        }
+
+        let without_commas = remove_trailing_commas(trimmed);
+        if without_commas.len() != trimmed.len() {
+            normalizations.push(Normalization::RemovedTrailingCommas);
+        }
+
+        Message::from_str(&without_commas).map(|message| (message, normalizations))
+    }
+
+    /// Parses a message of a sender which omits or garbles the message type
+    ///
+    /// If the message type is missing or not known, the input is tried as every known message type
+    /// in the order of [`MESSAGE_IDS`] and the first type it parses as wins.
+    /// A known message type is never replaced, such a message fails like in `from_str`.
+    /// Several types share the same data fields, so the result is a guess and must not be used
+    /// where the message type matters, e.g. to route a request.
+    pub fn from_str_best_effort(s: &str) -> Result<Message, Error> {
+        let error = match Message::from_str(s) {
+            Ok(message) => return Ok(message),
+            Err(error) => error,
+        };
+
+        let value: Value = match serde_json::from_str(s) {
+            Ok(value @ Value::Object(_)) => value,
+            _ => return Err(error),
+        };
+        let message_type = value.get(MESSAGE_TYPE_KEY).and_then(Value::as_i64);
+        if matches!(message_type, Some(id) if MESSAGE_IDS.contains(&id)) {
+            return Err(error);
+        }
+
+        for id in MESSAGE_IDS.iter() {
+            let mut candidate = value.clone();
+            candidate[MESSAGE_TYPE_KEY] = (*id).into();
+            if let Ok(message) = Message::from_str(&candidate.to_string()) {
+                return Ok(message);
+            }
+        }
+
+        Err(error.context("No message type matches the input"))
+    }
+}
+
+fn remove_trailing_commas(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (i, c) in s.char_indices() {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+        } else if c == '"' {
+            in_string = true;
+        } else if c == ',' {
+            let next = s[i + 1..].trim_start().chars().next();
+            if next == Some('}') || next == Some(']') {
+                continue;
+            }
+        }
+
+        result.push(c);
+    }
+
+    result
+}
@@ -0,0 +1,354 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+use std::str::FromStr;
+
+use serde_json::{Map, Value};
+
+use crate::{Error, Message, MessageBase, MESSAGE_IDS, MESSAGE_SCHEMA_FILES, MESSAGE_TYPE_KEY};
+
+impl Message {
+    /// Non-finite numbers become `null` like in all serializations through `serde_json`,
+    /// so the message serializes but a required number cannot be parsed back
+    pub fn to_value(&self) -> Result<Value, Error> {
+        serde_json::to_value(self)
+            .map_err(|e| Error::new(format!("Could not serialize message: {}", e)))
+    }
+
+    pub fn into_value(self) -> Result<Value, Error> {
+        self.to_value()
+    }
+
+    /// The field at the given JSON pointer of the serialized message, e.g. `/data/device/title`
+    ///
+    /// The message is serialized on every call, prefer the typed fields where the type is known.
+    pub fn get(&self, pointer: &str) -> Option<Value> {
+        self.to_value().ok()?.pointer_mut(pointer).map(Value::take)
+    }
+
+    /// Serializes the message as JSON indented by two spaces, see [`write_pretty`](crate::write_pretty)
+    pub fn to_string_pretty(&self) -> Result<String, Error> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| Error::new(format!("Could not serialize message: {}", e)))
+    }
+
+    /// Copy of the message for logging, longer strings are cut after `max_len` characters
+    ///
+    /// A cut string ends with an ellipsis and its original length.
+    pub fn truncated(&self, max_len: usize) -> Result<Message, Error> {
+        let mut value = self.to_value()?;
+        truncate_strings(&mut value, max_len);
+        Message::from_str_or_unknown(&value.to_string())
+    }
+
+    /// Overlays the fields of another message of the same type onto this one
+    ///
+    /// Objects are merged recursively, all other values including arrays are replaced.
+    /// Fields which are absent or `null` in `other` keep their current value,
+    /// so a field cannot be cleared through a merge.
+    pub fn merge_from(&mut self, other: &Message) -> Result<(), Error> {
+        if self.message_id() != other.message_id() {
+            return Err(Error::new(format!(
+                "Cannot merge message type {} into {}",
+                other.message_id(),
+                self.message_id()
+            )));
+        }
+
+        let mut value = self.to_value()?;
+        merge_values(&mut value, other.to_value()?);
+        *self = Message::from_str_or_unknown(&value.to_string())?;
+        Ok(())
+    }
+
+    /// Key for routing tables indexed by plugin and message type
+    pub fn routing_key(&self) -> (Option<&str>, i64) {
+        (self.plugin_id_opt(), self.message_id())
+    }
+
+    /// Key for routing tables indexed by plugin and device, `None` if the message has no device id
+    pub fn device_key(&self) -> Option<(&str, &str)> {
+        Some((self.plugin_id_opt()?, self.device_id()?))
+    }
+
+    /// Whether the message belongs to the connection of the given plugin
+    ///
+    /// `missing` decides about a message of an unknown type without a plugin id.
+    pub fn is_for_plugin(&self, plugin_id: &str, missing: MissingPluginId) -> bool {
+        match self.plugin_id_opt() {
+            Some(id) => id == plugin_id,
+            None => missing == MissingPluginId::Accept,
+        }
+    }
+
+    /// Returns the same message on behalf of another plugin
+    #[must_use]
+    pub fn with_plugin_id(mut self, plugin_id: &str) -> Message {
+        self.set_plugin_id(plugin_id);
+        self
+    }
+
+    /// Returns the same message with another correlation id, unchanged if it has none
+    #[must_use]
+    pub fn with_correlation_id(mut self, id: i64) -> Message {
+        self.set_correlation_id(id);
+        self
+    }
+
+    /// Splits the message into its type, plugin id and data, e.g. to store the envelope separately
+    ///
+    /// The data of messages defined at the top level of their schema are all fields except the message type.
+    pub fn into_parts(self) -> Result<(i64, Option<String>, Value), Error> {
+        let message_type = self.message_id();
+        let plugin_id = self.plugin_id_opt().map(str::to_owned);
+        let mut value = self.into_value()?;
+
+        let data = match value.get_mut("data") {
+            Some(data) => data.take(),
+            None => {
+                if let Some(map) = value.as_object_mut() {
+                    map.remove(MESSAGE_TYPE_KEY);
+                }
+                value
+            }
+        };
+
+        Ok((message_type, plugin_id, data))
+    }
+
+    /// Rebuilds a message from the parts returned by [`into_parts`](Message::into_parts)
+    pub fn from_parts(message_type: i64, data: Value) -> Result<Message, Error> {
+        // Messages defined at the top level of their schema have no data object
+        let top_level = match Message::schema_for_id(message_type)
+            .and_then(|schema| serde_json::from_str::<Value>(schema).ok())
+        {
+            Some(schema) => schema.pointer("/properties/data").is_none(),
+            None => false,
+        };
+
+        let mut value = match (top_level, data) {
+            (true, Value::Object(map)) => map,
+            (_, data) => {
+                let mut map = Map::new();
+                map.insert("data".to_owned(), data);
+                map
+            }
+        };
+        value.insert(MESSAGE_TYPE_KEY.to_owned(), message_type.into());
+
+        Message::from_str(&Value::Object(value).to_string())
+    }
+
+    /// The name and embedded JSON schema of every message type, e.g. for generating clients
+    ///
+    /// These are the schema files the types were generated from, including the schema overrides.
+    /// References to other schema files are not resolved.
+    pub fn schemas() -> Vec<(&'static str, Value)> {
+        MESSAGE_IDS
+            .iter()
+            .zip(MESSAGE_SCHEMA_FILES.iter())
+            .filter_map(|(id, (name, _))| {
+                let schema = serde_json::from_str(Message::schema_for_id(*id)?).ok()?;
+                Some((*name, schema))
+            })
+            .collect()
+    }
+
+    /// The data fields of the message with their schema metadata, e.g. to render a form
+    ///
+    /// Each field of the schema maps to an object with its current `value`, `null` if absent,
+    /// whether it is `required` and its schema `type`:
+    /// `{"messageType": 4, "fields": {"pluginId": {"value": "p", "required": true, "type": "string"}}}`
+    pub fn to_value_with_schema_metadata(&self) -> Result<Value, Error> {
+        let schema = Message::schema_for_id(self.message_id()).ok_or_else(|| {
+            Error::new(format!("No schema for message type {}", self.message_id()))
+        })?;
+        let schema: Value = serde_json::from_str(schema)
+            .map_err(|e| Error::new(format!("Invalid embedded schema: {}", e)))?;
+        let value = self.to_value()?;
+
+        // Messages defined at the top level of their schema have no data object
+        let (data_schema, data, top_level) = match schema.pointer("/properties/data") {
+            Some(data_schema) => (data_schema, value.get("data"), false),
+            None => (&schema, Some(&value), true),
+        };
+        let required = data_schema
+            .get("required")
+            .and_then(Value::as_array)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+
+        let mut fields = Map::new();
+        if let Some(properties) = data_schema.get("properties").and_then(Value::as_object) {
+            for (name, property) in properties {
+                if top_level && name == MESSAGE_TYPE_KEY {
+                    continue;
+                }
+                let mut field = Map::new();
+                field.insert(
+                    "value".to_owned(),
+                    data.and_then(|data| data.get(name))
+                        .cloned()
+                        .unwrap_or(Value::Null),
+                );
+                field.insert(
+                    "required".to_owned(),
+                    required.iter().any(|r| r == name).into(),
+                );
+                field.insert(
+                    "type".to_owned(),
+                    property.get("type").cloned().unwrap_or(Value::Null),
+                );
+                fields.insert(name.clone(), field.into());
+            }
+        }
+
+        let mut metadata = Map::new();
+        metadata.insert(MESSAGE_TYPE_KEY.to_owned(), self.message_id().into());
+        metadata.insert("fields".to_owned(), fields.into());
+        Ok(metadata.into())
+    }
+
+    /// Single line description for logs with the name, type, ids and serialized length
+    ///
+    /// E.g. `DeviceAddedNotification(4096) plugin="p" adapter="a" device="d" (412 bytes)`,
+    /// the ids are quoted and escaped so the description never spans several lines.
+    pub fn compact_debug(&self) -> String {
+        let mut line = format!("{}({})", self.name(), self.message_id());
+
+        if let Some(id) = self.plugin_id_opt() {
+            line += &format!(" plugin={:?}", id);
+        }
+        if let Some(id) = self.adapter_id() {
+            line += &format!(" adapter={:?}", id);
+        }
+        if let Some(id) = self.device_id() {
+            line += &format!(" device={:?}", id);
+        }
+        if let Some(id) = self.correlation_id() {
+            line += &format!(" correlation={}", id);
+        }
+        match serde_json::to_vec(self) {
+            Ok(json) => line += &format!(" ({} bytes)", json.len()),
+            Err(_) => line += " (not serializable)",
+        }
+
+        line
+    }
+
+    /// Fails if the message was sent on behalf of another plugin
+    ///
+    /// `missing` decides about a message of an unknown type without a plugin id.
+    pub fn ensure_plugin_id(&self, expected: &str, missing: MissingPluginId) -> Result<(), Error> {
+        match (self.plugin_id_opt(), missing) {
+            (Some(plugin_id), _) if plugin_id != expected => Err(Error::new(format!(
+                "Expected plugin id {} but got {}",
+                expected, plugin_id
+            ))),
+            (None, MissingPluginId::Reject) => Err(Error::new(format!(
+                "Expected plugin id {} but got none",
+                expected
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Panics if the message violates the schema of its type, no-op in release builds
+    ///
+    /// With the `validation` feature, the message is checked against its schema.
+    /// Without it, the message only has to parse back from its serialization,
+    /// e.g. a non-finite number in a required field fails.
+    /// Unknown messages have no schema, so they are not checked.
+    pub fn debug_assert_valid(&self) {
+        if cfg!(debug_assertions) {
+            if let Message::Unknown { .. } = self {
+                return;
+            }
+            #[cfg(feature = "validation")]
+            if let Err(errors) = self.validate() {
+                let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+                panic!("Invalid {}: {}", self.name(), errors.join(", "));
+            }
+            #[cfg(not(feature = "validation"))]
+            if let Err(e) = serde_json::to_string(self)
+                .map_err(|e| Error::new(format!("Could not serialize message: {}", e)))
+                .and_then(|json| Message::from_str(&json))
+            {
+                panic!("Invalid {}: {}", self.name(), e);
+            }
+        }
+    }
+}
+
+/// How the plugin id checks treat a message without a plugin id
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MissingPluginId {
+    /// There is nothing to check, so the message passes
+    Accept,
+    /// The message fails like one of another plugin
+    Reject,
+}
+
+fn truncate_strings(value: &mut Value, max_len: usize) {
+    match value {
+        Value::String(s) => {
+            let len = s.chars().count();
+            if len > max_len {
+                let cut: String = s.chars().take(max_len).collect();
+                *s = format!("{}… ({} chars)", cut, len);
+            }
+        }
+        Value::Array(values) => {
+            for value in values {
+                truncate_strings(value, max_len);
+            }
+        }
+        Value::Object(map) => {
+            for value in map.values_mut() {
+                truncate_strings(value, max_len);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn merge_values(value: &mut Value, other: Value) {
+    match (value, other) {
+        (_, Value::Null) => {}
+        (Value::Object(map), Value::Object(other)) => {
+            for (key, other) in other {
+                match map.get_mut(&key) {
+                    Some(value) => merge_values(value, other),
+                    None if other.is_null() => {}
+                    None => {
+                        map.insert(key, other);
+                    }
+                }
+            }
+        }
+        (value, other) => *value = other,
+    }
+}
+
+/// Whether the message is of the given type, e.g. `message_is!(msg, DeviceAddedNotification)`
+#[macro_export]
+macro_rules! message_is {
+    ($msg:expr, $name:ident) => {
+        matches!($msg, $crate::Message::$name(_))
+    };
+}
+
+/// The data of the message if it is of the given type, e.g. `message_data!(msg, DeviceAddedNotification)`
+///
+/// Borrows the message, so an owned message stays usable afterwards.
+#[macro_export]
+macro_rules! message_data {
+    ($msg:expr, $name:ident) => {
+        match &$msg {
+            $crate::Message::$name(msg) => Some(&msg.data),
+            _ => None,
+        }
+    };
+}
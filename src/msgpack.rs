@@ -0,0 +1,63 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+use std::str::FromStr;
+
+use serde_json::Value;
+
+use crate::{Error, GenericMessage, Message, MESSAGE_IDS};
+
+impl Message {
+    /// Encodes the message as MessagePack map with the same field names as the JSON encoding
+    pub fn to_msgpack(&self) -> Result<Vec<u8>, Error> {
+        rmp_serde::to_vec_named(self)
+            .map_err(|e| Error::new(format!("Could not encode MessagePack: {}", e)))
+    }
+
+    /// Decodes a message encoded by [`Message::to_msgpack`]
+    ///
+    /// The message type is dispatched on the JSON representation of the decoded map.
+    pub fn from_msgpack(bytes: &[u8]) -> Result<Message, Error> {
+        let value: Value = rmp_serde::from_slice(bytes)
+            .map_err(|e| Error::new(format!("Invalid MessagePack: {}", e)))?;
+        Message::from_str(&value.to_string())
+    }
+}
+
+/// Converts a JSON message to the encoding of [`Message::to_msgpack`] without parsing its data
+///
+/// Only the message type is validated, the data is streamed from one encoding into the other.
+pub fn transcode_json_to_msgpack(json: &str) -> Result<Vec<u8>, Error> {
+    let msg: GenericMessage =
+        serde_json::from_str(json).map_err(|e| Error::new(format!("Invalid JSON: {}", e)))?;
+    ensure_known_type(&msg)?;
+
+    let mut bytes = Vec::new();
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+    serde_transcode::transcode(&mut deserializer, &mut rmp_serde::Serializer::new(&mut bytes))
+        .map_err(|e| Error::new(format!("Could not encode MessagePack: {}", e)))?;
+    Ok(bytes)
+}
+
+/// Converts a MessagePack message to JSON, the reverse of [`transcode_json_to_msgpack`]
+pub fn transcode_msgpack_to_json(bytes: &[u8]) -> Result<String, Error> {
+    let msg: GenericMessage = rmp_serde::from_slice(bytes)
+        .map_err(|e| Error::new(format!("Invalid MessagePack: {}", e)))?;
+    ensure_known_type(&msg)?;
+
+    let mut json = Vec::new();
+    let mut deserializer = rmp_serde::Deserializer::from_read_ref(bytes);
+    serde_transcode::transcode(&mut deserializer, &mut serde_json::Serializer::new(&mut json))
+        .map_err(|e| Error::new(format!("Could not encode JSON: {}", e)))?;
+    String::from_utf8(json).map_err(|e| Error::new(format!("Could not encode JSON: {}", e)))
+}
+
+fn ensure_known_type(msg: &GenericMessage) -> Result<(), Error> {
+    if MESSAGE_IDS.contains(&msg.message_type) {
+        Ok(())
+    } else {
+        Err(Error::unknown_message_type(msg.message_type))
+    }
+}
@@ -0,0 +1,106 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+use std::convert::TryFrom;
+
+use serde_json::Value;
+
+use crate::Message;
+
+/// A property value by its JSON type, see [`Message::typed_property`]
+#[derive(Clone, PartialEq, Debug)]
+pub enum TypedPropertyValue {
+    Boolean(bool),
+    Integer(i64),
+    Number(f64),
+    String(String),
+    /// Any other value, e.g. `null`, an array or an object
+    Object(Value),
+}
+
+impl From<Value> for TypedPropertyValue {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Bool(value) => TypedPropertyValue::Boolean(value),
+            Value::Number(number) => match (number.as_i64(), number.as_f64()) {
+                (Some(value), _) => TypedPropertyValue::Integer(value),
+                (None, Some(value)) => TypedPropertyValue::Number(value),
+                (None, None) => TypedPropertyValue::Object(Value::Number(number)),
+            },
+            Value::String(value) => TypedPropertyValue::String(value),
+            value => TypedPropertyValue::Object(value),
+        }
+    }
+}
+
+impl From<TypedPropertyValue> for Value {
+    fn from(value: TypedPropertyValue) -> Self {
+        match value {
+            TypedPropertyValue::Boolean(value) => value.into(),
+            TypedPropertyValue::Integer(value) => value.into(),
+            TypedPropertyValue::Number(value) => value.into(),
+            TypedPropertyValue::String(value) => value.into(),
+            TypedPropertyValue::Object(value) => value,
+        }
+    }
+}
+
+/// Returns the value unchanged if it is not a boolean
+impl TryFrom<TypedPropertyValue> for bool {
+    type Error = TypedPropertyValue;
+
+    fn try_from(value: TypedPropertyValue) -> Result<Self, Self::Error> {
+        match value {
+            TypedPropertyValue::Boolean(value) => Ok(value),
+            value => Err(value),
+        }
+    }
+}
+
+/// Returns the value unchanged if it is not an integer
+impl TryFrom<TypedPropertyValue> for i64 {
+    type Error = TypedPropertyValue;
+
+    fn try_from(value: TypedPropertyValue) -> Result<Self, Self::Error> {
+        match value {
+            TypedPropertyValue::Integer(value) => Ok(value),
+            value => Err(value),
+        }
+    }
+}
+
+/// Integers are accepted as well, as a number property may be sent without a fraction
+impl TryFrom<TypedPropertyValue> for f64 {
+    type Error = TypedPropertyValue;
+
+    fn try_from(value: TypedPropertyValue) -> Result<Self, Self::Error> {
+        match value {
+            TypedPropertyValue::Number(value) => Ok(value),
+            TypedPropertyValue::Integer(value) => Ok(value as f64),
+            value => Err(value),
+        }
+    }
+}
+
+/// Returns the value unchanged if it is not a string
+impl TryFrom<TypedPropertyValue> for String {
+    type Error = TypedPropertyValue;
+
+    fn try_from(value: TypedPropertyValue) -> Result<Self, Self::Error> {
+        match value {
+            TypedPropertyValue::String(value) => Ok(value),
+            value => Err(value),
+        }
+    }
+}
+
+impl Message {
+    /// Like [`property`](Message::property), but with the value converted by its JSON type
+    pub fn typed_property(&self) -> Option<(&str, TypedPropertyValue)> {
+        let (name, value) = self.property()?;
+        let value = serde_json::to_value(value).ok()?;
+        Some((name, value.into()))
+    }
+}
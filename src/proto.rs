@@ -0,0 +1,62 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+use std::{
+    convert::TryFrom,
+    str::{self, FromStr},
+};
+
+use crate::{Error, Message, MessageBase};
+
+/// Protobuf envelope carrying the message type and the JSON encoded message
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ProtoMessage {
+    #[prost(int64, tag = "1")]
+    pub message_type: i64,
+    #[prost(bytes = "vec", tag = "2")]
+    pub json: Vec<u8>,
+}
+
+impl TryFrom<&Message> for ProtoMessage {
+    type Error = Error;
+
+    fn try_from(message: &Message) -> Result<Self, Self::Error> {
+        let json = serde_json::to_vec(message)
+            .map_err(|e| Error::new(format!("Could not serialize message: {}", e)))?;
+
+        Ok(Self {
+            message_type: message.message_id(),
+            json,
+        })
+    }
+}
+
+impl TryFrom<Message> for ProtoMessage {
+    type Error = Error;
+
+    fn try_from(message: Message) -> Result<Self, Self::Error> {
+        Self::try_from(&message)
+    }
+}
+
+impl TryFrom<ProtoMessage> for Message {
+    type Error = Error;
+
+    fn try_from(proto: ProtoMessage) -> Result<Self, Self::Error> {
+        let json =
+            str::from_utf8(&proto.json).map_err(|e| Error::new(format!("Invalid UTF-8: {}", e)))?;
+        let message = Message::from_str(json)?;
+
+        if message.message_id() != proto.message_type {
+            return Err(Error::new(format!(
+                "Envelope message type {} does not match message type {}",
+                proto.message_type,
+                message.message_id()
+            )));
+        }
+
+        Ok(message)
+    }
+}
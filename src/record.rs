@@ -0,0 +1,36 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+use std::time::SystemTime;
+
+use serde::Serialize;
+
+use crate::{Direction, Message, MessageBase};
+
+/// Flat summary of a message for structured logging, `ts` is the time of the conversion
+#[derive(Clone, PartialEq, Debug, Serialize)]
+pub struct MessageLogRecord {
+    pub ts: SystemTime,
+    pub direction: Option<Direction>,
+    pub name: &'static str,
+    pub message_type: i64,
+    pub plugin_id: Option<String>,
+    pub device_id: Option<String>,
+    pub adapter_id: Option<String>,
+}
+
+impl From<&Message> for MessageLogRecord {
+    fn from(message: &Message) -> Self {
+        Self {
+            ts: SystemTime::now(),
+            direction: message.direction(),
+            name: message.name(),
+            message_type: message.message_id(),
+            plugin_id: message.plugin_id_opt().map(str::to_owned),
+            device_id: message.device_id().map(str::to_owned),
+            adapter_id: message.adapter_id().map(str::to_owned),
+        }
+    }
+}
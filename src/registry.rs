@@ -0,0 +1,91 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+use std::{collections::BTreeMap, str::FromStr};
+
+use serde_json::Value;
+
+use crate::{Error, Message, MESSAGE_IDS, MESSAGE_TYPE_KEY};
+
+pub type ParseFn<T> = fn(&str) -> Result<T, Error>;
+pub type SerializeFn<T> = fn(&T) -> Result<String, Error>;
+
+#[derive(Debug)]
+pub enum RegistryMessage<T> {
+    Builtin(Message),
+    Custom { message_type: i64, message: T },
+}
+
+/// Dispatches to the generated message types first and to the registered custom types after that
+pub struct MessageRegistry<T> {
+    custom: BTreeMap<i64, (ParseFn<T>, SerializeFn<T>)>,
+}
+
+impl<T> Default for MessageRegistry<T> {
+    fn default() -> Self {
+        Self {
+            custom: BTreeMap::new(),
+        }
+    }
+}
+
+impl<T> MessageRegistry<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fails if the message type is already taken by a generated or registered type
+    pub fn register(
+        &mut self,
+        message_type: i64,
+        parse: ParseFn<T>,
+        serialize: SerializeFn<T>,
+    ) -> Result<(), Error> {
+        if MESSAGE_IDS.contains(&message_type) || self.custom.contains_key(&message_type) {
+            return Err(Error::new(format!(
+                "Message type {} is already registered",
+                message_type
+            )));
+        }
+
+        self.custom.insert(message_type, (parse, serialize));
+        Ok(())
+    }
+
+    pub fn from_str(&self, s: &str) -> Result<RegistryMessage<T>, Error> {
+        let value: Value =
+            serde_json::from_str(s).map_err(|e| Error::new(format!("Invalid message: {}", e)))?;
+        let message_type = value
+            .get(MESSAGE_TYPE_KEY)
+            .and_then(Value::as_i64)
+            .ok_or_else(|| Error::new(format!("Invalid message: missing {}", MESSAGE_TYPE_KEY)))?;
+
+        if MESSAGE_IDS.contains(&message_type) {
+            return Message::from_str(s).map(RegistryMessage::Builtin);
+        }
+
+        match self.custom.get(&message_type) {
+            Some((parse, _)) => parse(s).map(|message| RegistryMessage::Custom {
+                message_type,
+                message,
+            }),
+            None => Err(Error::unknown_message_type(message_type)),
+        }
+    }
+
+    pub fn to_string(&self, message: &RegistryMessage<T>) -> Result<String, Error> {
+        match message {
+            RegistryMessage::Builtin(message) => serde_json::to_string(message)
+                .map_err(|e| Error::new(format!("Could not serialize message: {}", e))),
+            RegistryMessage::Custom {
+                message_type,
+                message,
+            } => match self.custom.get(message_type) {
+                Some((_, serialize)) => serialize(message),
+                None => Err(Error::unknown_message_type(*message_type)),
+            },
+        }
+    }
+}
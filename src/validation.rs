@@ -0,0 +1,165 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+use std::{
+    collections::HashMap,
+    fmt::{self, Display, Formatter},
+    sync::OnceLock,
+};
+
+use jsonschema::{Retrieve, Uri, Validator};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    Message, MessageType, MESSAGE_IDS, MESSAGE_SCHEMA_FILES, MESSAGE_TYPE_KEY,
+    SCHEMA_REPOSITORY_FILES,
+};
+
+/// The base of the schema file URIs, the references between the files are relative to it
+const SCHEMA_BASE_URI: &str = "file:///gateway-addon-ipc-schema/";
+
+/// A violation of the schema of a message
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ValidationError {
+    /// The JSON pointer to the offending value, empty for the message itself
+    pub instance_path: String,
+    /// The JSON pointer to the violated keyword in the schema
+    pub schema_path: String,
+    pub message: String,
+}
+
+impl ValidationError {
+    fn new(message: String) -> Self {
+        Self {
+            instance_path: "".to_owned(),
+            schema_path: "".to_owned(),
+            message,
+        }
+    }
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.instance_path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.instance_path, self.message)
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Resolves the references of the message schemas to the embedded schema files,
+/// so validating never reads the file system or the network.
+struct EmbeddedSchemas;
+
+impl Retrieve for EmbeddedSchemas {
+    fn retrieve(
+        &self,
+        uri: &Uri<String>,
+    ) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
+        let file = uri
+            .as_str()
+            .strip_prefix(SCHEMA_BASE_URI)
+            .ok_or_else(|| format!("Schema {} is not part of the schema repository", uri))?;
+        let (_, json) = SCHEMA_REPOSITORY_FILES
+            .iter()
+            .find(|(path, _)| *path == file)
+            .ok_or_else(|| format!("Schema file {} not found", file))?;
+        Ok(serde_json::from_str(json)?)
+    }
+}
+
+/// The compiled schema of every message type, built on first use
+fn validators() -> &'static HashMap<i64, Result<Validator, String>> {
+    static VALIDATORS: OnceLock<HashMap<i64, Result<Validator, String>>> = OnceLock::new();
+
+    VALIDATORS.get_or_init(|| {
+        MESSAGE_IDS
+            .iter()
+            .zip(MESSAGE_SCHEMA_FILES.iter())
+            .map(|(id, (_, file))| {
+                let validator = Message::schema_for_id(*id)
+                    .ok_or_else(|| format!("No schema for message type {}", id))
+                    .and_then(|schema| serde_json::from_str(schema).map_err(|e| e.to_string()))
+                    .and_then(|schema: Value| {
+                        jsonschema::options()
+                            .with_base_uri(format!("{}{}", SCHEMA_BASE_URI, file))
+                            .with_retriever(EmbeddedSchemas)
+                            .build(&schema)
+                            .map_err(|e| e.to_string())
+                    });
+                (*id, validator)
+            })
+            .collect()
+    })
+}
+
+impl Message {
+    /// Checks the message against the schema of its type, see [`Message::validate_value`]
+    pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
+        let value = self
+            .to_value()
+            .map_err(|e| vec![ValidationError::new(e.to_string())])?;
+        Message::validate_value(&value)
+    }
+
+    /// Checks a JSON message against the schema of the type in its `messageType`
+    ///
+    /// Unlike parsing, this reports every violation with its location,
+    /// including the constraints the generated types do not check, e.g. patterns.
+    /// Messages of unknown types have no schema and fail.
+    pub fn validate_value(value: &Value) -> Result<(), Vec<ValidationError>> {
+        let message_type = value
+            .get(MESSAGE_TYPE_KEY)
+            .and_then(Value::as_i64)
+            .ok_or_else(|| {
+                vec![ValidationError::new(format!(
+                    "Expected an integer {}",
+                    MESSAGE_TYPE_KEY
+                ))]
+            })?;
+        let validator = validators()
+            .get(&message_type)
+            .ok_or_else(|| {
+                vec![ValidationError::new(format!(
+                    "No schema for message type {}",
+                    message_type
+                ))]
+            })?
+            .as_ref()
+            .map_err(|e| vec![ValidationError::new(format!("Invalid schema: {}", e))])?;
+
+        let errors: Vec<ValidationError> = validator
+            .iter_errors(value)
+            .map(|error| ValidationError {
+                instance_path: error.instance_path().as_str().to_owned(),
+                schema_path: error.schema_path().as_str().to_owned(),
+                message: error.to_string(),
+            })
+            .collect();
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Checks a message struct against the schema of its type
+pub trait Validate {
+    fn validate(&self) -> Result<(), Vec<ValidationError>>;
+}
+
+impl<T: MessageType + Serialize> Validate for T {
+    fn validate(&self) -> Result<(), Vec<ValidationError>> {
+        let value =
+            serde_json::to_value(self).map_err(|e| vec![ValidationError::new(e.to_string())])?;
+        Message::validate_value(&value)
+    }
+}
@@ -0,0 +1,45 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+#[allow(dead_code)]
+mod common;
+
+use serde_json::Value;
+use webthings_gateway_ipc_types::MESSAGE_IDS;
+
+/// More digits than an `f64` can hold
+const PRECISE: &str = "3.141592653589793238462643383279";
+
+/// Replaces the numbers of the instance with [`PRECISE`], returns how many were replaced
+fn make_precise(value: &mut Value) -> usize {
+    match value {
+        Value::Number(number) if number.is_f64() => {
+            *value = serde_json::from_str(PRECISE).unwrap();
+            1
+        }
+        Value::Array(items) => items.iter_mut().map(make_precise).sum(),
+        Value::Object(object) => object.values_mut().map(make_precise).sum(),
+        _ => 0,
+    }
+}
+
+#[test]
+fn numbers_keep_their_precision() {
+    let mut replaced = 0;
+
+    for id in MESSAGE_IDS.iter() {
+        let mut value = common::instance(*id);
+        let count = make_precise(&mut value);
+        if count == 0 {
+            continue;
+        }
+        replaced += count;
+
+        let serialized = common::round_trip(&value).to_string();
+        assert_eq!(serialized.matches(PRECISE).count(), count, "{}", serialized);
+    }
+
+    assert!(replaced > 0, "No message has a number field");
+}
@@ -0,0 +1,211 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+use std::{io::Cursor, str::FromStr, time::Duration};
+
+use tokio::{
+    io::{duplex, AsyncBufReadExt, AsyncWriteExt, BufReader},
+    sync::mpsc,
+    time::{sleep, Instant},
+};
+use webthings_gateway_ipc_types::{
+    replay_messages, run_plugin, run_plugin_with_heartbeat, spawn_reader, AsyncMessageHandler,
+    Error, Heartbeat, Message, MessageBase, MessageType, PluginRegisterRequest,
+    PluginRegisterResponse, PluginUnloadRequest, PluginUnloadResponse,
+};
+
+#[allow(dead_code)]
+mod common;
+
+fn frame(plugin_id: &str) -> String {
+    format!(
+        "{{\"messageType\":{},\"data\":{{\"pluginId\":\"{}\"}}}}\n",
+        PluginUnloadRequest::MESSAGE_ID,
+        plugin_id
+    )
+}
+
+#[tokio::test]
+async fn slow_consumer_pauses_the_reader() {
+    let input: String = (0..10).map(|i| frame(&format!("p{}", i))).collect();
+    let (tx, mut rx) = mpsc::channel(1);
+    let reader = spawn_reader(Cursor::new(input.into_bytes()), tx);
+
+    sleep(Duration::from_millis(50)).await;
+    assert!(!reader.is_finished());
+
+    for i in 0..10 {
+        let message = rx.recv().await.unwrap().unwrap();
+        assert_eq!(message.plugin_id(), format!("p{}", i));
+        sleep(Duration::from_millis(5)).await;
+    }
+    assert!(rx.recv().await.is_none());
+    reader.await.unwrap();
+}
+
+#[tokio::test]
+async fn invalid_message_is_sent_as_error() {
+    let input = frame("a") + "{\n" + &frame("b");
+    let (tx, mut rx) = mpsc::channel(4);
+    spawn_reader(Cursor::new(input.into_bytes()), tx);
+
+    assert_eq!(rx.recv().await.unwrap().unwrap().plugin_id(), "a");
+    assert!(rx.recv().await.unwrap().is_err());
+    assert_eq!(rx.recv().await.unwrap().unwrap().plugin_id(), "b");
+    assert!(rx.recv().await.is_none());
+}
+
+#[tokio::test]
+async fn stream_ending_within_a_frame_fails() {
+    let input = frame("a");
+    let (tx, mut rx) = mpsc::channel(4);
+    spawn_reader(Cursor::new(input.trim_end().as_bytes().to_vec()), tx);
+
+    assert!(rx.recv().await.unwrap().is_err());
+    assert!(rx.recv().await.is_none());
+}
+
+#[derive(Default)]
+struct RecordingHandler {
+    messages: Vec<i64>,
+    frame_errors: usize,
+}
+
+impl AsyncMessageHandler for &mut RecordingHandler {
+    async fn handle_message(&mut self, message: Message) -> Result<Vec<Message>, Error> {
+        self.messages.push(message.message_id());
+        Ok(Vec::new())
+    }
+
+    fn handle_frame_error(&mut self, _: Error) -> Result<(), Error> {
+        self.frame_errors += 1;
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn plugin_runs_until_unloaded() {
+    let (plugin, gateway) = duplex(64 * 1024);
+    let mut handler = RecordingHandler::default();
+    let plugin =
+        tokio::spawn(async move { run_plugin(plugin, "p", &mut handler).await.map(|_| handler) });
+
+    let (reader, mut writer) = tokio::io::split(gateway);
+    let mut lines = BufReader::new(reader).lines();
+    let request = Message::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+    assert_eq!(request.message_id(), PluginRegisterRequest::MESSAGE_ID);
+    assert_eq!(request.plugin_id(), "p");
+
+    let response = common::instance(PluginRegisterResponse::MESSAGE_ID).to_string() + "\n";
+    writer.write_all(response.as_bytes()).await.unwrap();
+    writer.write_all(b"{\n").await.unwrap();
+    writer.write_all(frame("p").as_bytes()).await.unwrap();
+
+    let response = Message::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+    assert_eq!(response.message_id(), PluginUnloadResponse::MESSAGE_ID);
+    let handler = plugin.await.unwrap().unwrap();
+    assert_eq!(handler.messages, vec![PluginUnloadRequest::MESSAGE_ID]);
+    assert_eq!(handler.frame_errors, 1);
+}
+
+#[tokio::test]
+async fn plugin_fails_without_register_response() {
+    let (plugin, gateway) = duplex(64 * 1024);
+    drop(gateway);
+
+    assert!(run_plugin(plugin, "p", &mut RecordingHandler::default())
+        .await
+        .is_err());
+}
+
+#[tokio::test(start_paused = true)]
+async fn replay_is_paced() {
+    let messages: Vec<Message> = (0..5)
+        .map(|i| Message::from_str(frame(&format!("p{}", i)).trim_end()).unwrap())
+        .collect();
+    let mut output = Vec::new();
+    let start = Instant::now();
+
+    replay_messages(&mut output, &messages, 10).await.unwrap();
+
+    // The first frame is written right away, the others 100 ms apart
+    assert_eq!(start.elapsed(), Duration::from_millis(400));
+    let plugin_ids: Vec<String> = String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|line| Message::from_str(line).unwrap().plugin_id().to_owned())
+        .collect();
+    assert_eq!(plugin_ids, vec!["p0", "p1", "p2", "p3", "p4"]);
+}
+
+#[tokio::test]
+async fn replay_at_zero_rate_fails() {
+    assert!(replay_messages(&mut Vec::new(), &[], 0).await.is_err());
+}
+
+const HEARTBEAT: Heartbeat = Heartbeat {
+    interval: Duration::from_secs(10),
+    timeout: Duration::from_secs(5),
+};
+
+/// Registers the plugin on the gateway side of the stream
+async fn register<R, W>(lines: &mut tokio::io::Lines<BufReader<R>>, writer: &mut W)
+where
+    R: tokio::io::AsyncRead + Unpin,
+    W: tokio::io::AsyncWrite + Unpin,
+{
+    let request = Message::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+    assert_eq!(request.message_id(), PluginRegisterRequest::MESSAGE_ID);
+    let response = common::instance(PluginRegisterResponse::MESSAGE_ID).to_string() + "\n";
+    writer.write_all(response.as_bytes()).await.unwrap();
+}
+
+#[tokio::test(start_paused = true)]
+async fn missed_pong_fails() {
+    let (plugin, gateway) = duplex(64 * 1024);
+    let start = Instant::now();
+    let plugin = tokio::spawn(async move {
+        run_plugin_with_heartbeat(plugin, "p", &mut RecordingHandler::default(), HEARTBEAT).await
+    });
+
+    let (reader, mut writer) = tokio::io::split(gateway);
+    let mut lines = BufReader::new(reader).lines();
+    register(&mut lines, &mut writer).await;
+    let ping = lines.next_line().await.unwrap().unwrap();
+    assert!(Message::from_str_or_unknown(&ping).unwrap().is_ping());
+
+    let error = plugin.await.unwrap().unwrap_err();
+    assert!(error.to_string().contains("No pong"));
+    assert_eq!(start.elapsed(), HEARTBEAT.interval + HEARTBEAT.timeout);
+}
+
+#[tokio::test(start_paused = true)]
+async fn answered_pings_keep_the_plugin_running() {
+    let (plugin, gateway) = duplex(64 * 1024);
+    let plugin = tokio::spawn(async move {
+        run_plugin_with_heartbeat(plugin, "p", &mut RecordingHandler::default(), HEARTBEAT).await
+    });
+
+    let (reader, mut writer) = tokio::io::split(gateway);
+    let mut lines = BufReader::new(reader).lines();
+    register(&mut lines, &mut writer).await;
+    let pong = serde_json::to_string(&Message::pong()).unwrap() + "\n";
+    for _ in 0..3 {
+        let ping = lines.next_line().await.unwrap().unwrap();
+        assert!(Message::from_str_or_unknown(&ping).unwrap().is_ping());
+        writer.write_all(pong.as_bytes()).await.unwrap();
+    }
+
+    // The plugin answers pings of the gateway as well
+    let ping = serde_json::to_string(&Message::ping()).unwrap() + "\n";
+    writer.write_all(ping.as_bytes()).await.unwrap();
+    let answer = lines.next_line().await.unwrap().unwrap();
+    assert!(Message::from_str_or_unknown(&answer).unwrap().is_pong());
+
+    writer.write_all(frame("p").as_bytes()).await.unwrap();
+    let response = Message::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+    assert_eq!(response.message_id(), PluginUnloadResponse::MESSAGE_ID);
+    plugin.await.unwrap().unwrap();
+}
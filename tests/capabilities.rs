@@ -0,0 +1,45 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+use std::str::FromStr;
+
+use webthings_gateway_ipc_types::{
+    Capabilities, Message, MessageType, PluginUnloadRequest, CAPABILITIES_MESSAGE_TYPE,
+};
+
+#[test]
+fn capabilities_round_trip_through_a_message() {
+    let ids = [PluginUnloadRequest::MESSAGE_ID, 1_000_000];
+    let json = serde_json::to_string(&Message::capabilities_from_supported(&ids)).unwrap();
+
+    let message = Message::from_str_or_unknown(&json).unwrap();
+
+    assert_eq!(
+        message.capabilities(),
+        Some(Capabilities::from_supported(&ids))
+    );
+    assert_eq!(
+        Message::from_str(&json)
+            .unwrap_err()
+            .unknown_message_type_id(),
+        Some(CAPABILITIES_MESSAGE_TYPE)
+    );
+}
+
+#[test]
+fn other_messages_have_no_capabilities() {
+    let json = format!(
+        r#"{{"messageType":{},"data":{{"pluginId":"p"}}}}"#,
+        PluginUnloadRequest::MESSAGE_ID
+    );
+
+    assert_eq!(Message::from_str(&json).unwrap().capabilities(), None);
+    assert_eq!(
+        Message::from_str_or_unknown(r#"{"messageType":-1,"data":{}}"#)
+            .unwrap()
+            .capabilities(),
+        None
+    );
+}
@@ -0,0 +1,191 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use serde_json::{Map, Value};
+use webthings_gateway_ipc_types::{
+    Message, MessageBase, MESSAGE_IDS, MESSAGE_SCHEMA_FILES, MESSAGE_TYPE_KEY,
+};
+
+/// A message of the given type with every property of its schema set, as JSON
+pub fn instance(message_type: i64) -> Value {
+    Instance::new(message_type, true).value
+}
+
+/// A message built from the schema of its type, with the fields the tests look at
+///
+/// The schema files are read from the schema repository the types were generated from,
+/// so references to other files can be resolved.
+/// The fields are JSON pointers into `value`.
+pub struct Instance {
+    pub value: Value,
+    /// String fields restricted to a set of values, with their values
+    pub enums: Vec<(String, Vec<Value>)>,
+    /// Optional properties which are not set
+    pub omitted: Vec<String>,
+    /// Required properties with a default which are not set, with their default
+    pub defaults: Vec<(String, Value)>,
+}
+
+impl Instance {
+    /// Without `all_properties`, only the required properties without default are set
+    pub fn new(message_type: i64, all_properties: bool) -> Self {
+        let index = MESSAGE_IDS
+            .iter()
+            .position(|id| *id == message_type)
+            .unwrap_or_else(|| panic!("Unknown message type {}", message_type));
+        let file = schema_dir().join(MESSAGE_SCHEMA_FILES[index].1);
+
+        let mut instance = Instance {
+            value: Value::Null,
+            enums: Vec::new(),
+            omitted: Vec::new(),
+            defaults: Vec::new(),
+        };
+        let mut value = instance.build(&read_schema(&file), &file, "", all_properties);
+        if let Some(map) = value.as_object_mut() {
+            map.insert(MESSAGE_TYPE_KEY.to_owned(), message_type.into());
+        }
+        instance.value = value;
+        instance
+    }
+
+    /// `file` is the schema file `schema` is part of, which references are relative to
+    fn build(&mut self, schema: &Value, file: &Path, pointer: &str, all_properties: bool) -> Value {
+        assert!(
+            pointer.matches('/').count() < 32,
+            "Schema of {} is recursive",
+            file.display()
+        );
+
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            let (path, target) = match reference.find('#') {
+                Some(index) => (&reference[..index], &reference[index + 1..]),
+                None => (reference, ""),
+            };
+            let file = match path {
+                "" => file.to_owned(),
+                path => file.parent().expect("Schema file has parent").join(path),
+            };
+            let root = read_schema(&file);
+            let target = root
+                .pointer(target)
+                .unwrap_or_else(|| panic!("No {} in {}", target, file.display()));
+            return self.build(target, &file, pointer, all_properties);
+        }
+        if let Some(value) = schema.get("const") {
+            return value.clone();
+        }
+        if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+            if values.iter().all(Value::is_string) {
+                self.enums.push((pointer.to_owned(), values.clone()));
+            }
+            if let Some(value) = values.first() {
+                return value.clone();
+            }
+        }
+        for key in &["oneOf", "anyOf", "allOf"] {
+            if let Some(first) = schema
+                .get(*key)
+                .and_then(Value::as_array)
+                .and_then(|schemas| schemas.first())
+            {
+                return self.build(first, file, pointer, all_properties);
+            }
+        }
+
+        let schema_type = match schema.get("type") {
+            Some(Value::Array(types)) => {
+                types.iter().filter_map(Value::as_str).find(|t| *t != "null")
+            }
+            Some(schema_type) => schema_type.as_str(),
+            None if schema.get("properties").is_some() => Some("object"),
+            None => None,
+        };
+        match schema_type {
+            Some("object") => {
+                let required: Vec<&str> = schema
+                    .get("required")
+                    .and_then(Value::as_array)
+                    .map(|required| required.iter().filter_map(Value::as_str).collect())
+                    .unwrap_or_default();
+                let mut map = Map::new();
+                if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+                    for (name, property) in properties {
+                        let pointer = format!(
+                            "{}/{}",
+                            pointer,
+                            name.replace('~', "~0").replace('/', "~1")
+                        );
+                        let default = property.get("default");
+                        if !all_properties && !required.contains(&name.as_str()) {
+                            self.omitted.push(pointer);
+                        } else if let (false, Some(default)) = (all_properties, default) {
+                            self.defaults.push((pointer, default.clone()));
+                        } else {
+                            let value = self.build(property, file, &pointer, all_properties);
+                            map.insert(name.clone(), value);
+                        }
+                    }
+                }
+                Value::Object(map)
+            }
+            Some("array") => match schema.get("items") {
+                Some(items) => Value::Array(vec![self.build(
+                    items,
+                    file,
+                    &format!("{}/0", pointer),
+                    all_properties,
+                )]),
+                None => Value::Array(Vec::new()),
+            },
+            Some("string") => "x".into(),
+            Some("integer") => 1.into(),
+            Some("number") => 1.5.into(),
+            Some("boolean") => true.into(),
+            Some("null") => Value::Null,
+            // Untyped fields are generated as `Value`, which holds anything
+            _ => "x".into(),
+        }
+    }
+}
+
+/// Parses the instance of the message type and checks that its serialization parses back the same
+pub fn assert_round_trip(message_type: i64) {
+    let json = instance(message_type).to_string();
+    let message =
+        Message::from_str(&json).unwrap_or_else(|e| panic!("Could not parse {}: {}", json, e));
+    assert_eq!(message.message_id(), message_type);
+
+    let serialized = serde_json::to_string(&message).unwrap();
+    let parsed = Message::from_str(&serialized)
+        .unwrap_or_else(|e| panic!("Could not parse back {}: {}", serialized, e));
+    assert_eq!(parsed.message_id(), message_type);
+    assert_eq!(parsed.name(), message.name());
+    assert_eq!(serde_json::to_string(&parsed).unwrap(), serialized);
+}
+
+/// Parses the message and serializes it again
+pub fn round_trip(value: &Value) -> Value {
+    let message = Message::from_str(&value.to_string())
+        .unwrap_or_else(|e| panic!("Could not parse {}: {}", value, e));
+    serde_json::to_value(&message).unwrap()
+}
+
+fn schema_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("gateway-addon-ipc-schema")
+}
+
+fn read_schema(file: &Path) -> Value {
+    let content = fs::read_to_string(file)
+        .unwrap_or_else(|e| panic!("Read schema file {}: {}", file.display(), e));
+    serde_json::from_str(&content)
+        .unwrap_or_else(|e| panic!("Parse JSON schema {}: {}", file.display(), e))
+}
@@ -0,0 +1,29 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+use std::str::FromStr;
+
+use webthings_gateway_ipc_types::{Message, MessageType, PluginUnloadRequest};
+
+#[test]
+fn message_debug_shows_name_and_data() {
+    let json = format!(
+        r#"{{"messageType":{},"data":{{"pluginId":"p"}}}}"#,
+        PluginUnloadRequest::MESSAGE_ID
+    );
+    let message = Message::from_str(&json).unwrap();
+
+    assert_eq!(
+        format!("{:?}", message),
+        r#"Message::PluginUnloadRequest { plugin_id: "p" }"#
+    );
+}
+
+#[test]
+fn unknown_message_debug_shows_type_and_raw() {
+    let message = Message::from_str_or_unknown(r#"{"messageType":-1,"data":{}}"#).unwrap();
+
+    assert!(format!("{:?}", message).starts_with("Message::Unknown { message_type: -1, raw: "));
+}
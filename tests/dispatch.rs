@@ -0,0 +1,66 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+#[allow(dead_code)]
+mod common;
+
+use std::str::FromStr;
+
+use webthings_gateway_ipc_types::{
+    GenericMessage, Message, MessageBase, MESSAGE_IDS, MESSAGE_SCHEMA_FILES, MESSAGE_TYPE_KEY,
+};
+
+#[test]
+fn every_message_id_dispatches_to_its_type() {
+    for (id, (name, _)) in MESSAGE_IDS.iter().zip(MESSAGE_SCHEMA_FILES.iter()) {
+        let message = Message::from_str(&common::instance(*id).to_string())
+            .unwrap_or_else(|e| panic!("Could not parse {}: {}", name, e));
+
+        assert_eq!(message.message_id(), *id);
+        assert_eq!(message.name(), *name);
+        assert!(Message::schema_for_id(*id).is_some());
+    }
+}
+
+/// The variant is chosen by the message type, not by which type's fields the data matches
+#[test]
+fn message_type_decides_the_variant() {
+    for id in MESSAGE_IDS.iter() {
+        let value = common::instance(*id);
+
+        for other in MESSAGE_IDS.iter().filter(|other| *other != id) {
+            let mut value = value.clone();
+            value[MESSAGE_TYPE_KEY] = (*other).into();
+
+            if let Ok(message) = Message::from_str(&value.to_string()) {
+                assert_eq!(message.message_id(), *other);
+            }
+        }
+    }
+}
+
+#[test]
+fn peeked_message_parses() {
+    for id in MESSAGE_IDS.iter() {
+        let json = common::instance(*id).to_string();
+        let peeked: GenericMessage = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(peeked.into_message(&json).unwrap().message_id(), *id);
+    }
+}
+
+#[test]
+fn peeked_message_type_has_to_match_the_input() {
+    let peeked: GenericMessage =
+        serde_json::from_str(&common::instance(MESSAGE_IDS[0]).to_string()).unwrap();
+    let mut other = common::instance(MESSAGE_IDS[1]);
+
+    assert!(peeked.into_message(&other.to_string()).is_err());
+
+    other.as_object_mut().unwrap().remove(MESSAGE_TYPE_KEY);
+    let peeked: GenericMessage =
+        serde_json::from_str(&common::instance(MESSAGE_IDS[0]).to_string()).unwrap();
+    assert!(peeked.into_message(&other.to_string()).is_err());
+}
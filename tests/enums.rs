@@ -0,0 +1,37 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+#[allow(dead_code)]
+mod common;
+
+use common::Instance;
+use serde_json::Value;
+use webthings_gateway_ipc_types::MESSAGE_IDS;
+
+fn assert_enum_round_trips(value: impl Fn(&[Value]) -> Vec<Value>) {
+    for id in MESSAGE_IDS.iter() {
+        let instance = Instance::new(*id, true);
+
+        for (pointer, values) in &instance.enums {
+            for value in value(values) {
+                let mut message = instance.value.clone();
+                *message.pointer_mut(pointer).unwrap() = value.clone();
+
+                let serialized = common::round_trip(&message);
+                assert_eq!(serialized.pointer(pointer), Some(&value), "{}", pointer);
+            }
+        }
+    }
+}
+
+#[test]
+fn every_enum_value_round_trips() {
+    assert_enum_round_trips(|values| values.to_vec());
+}
+
+#[test]
+fn unknown_enum_value_is_kept() {
+    assert_enum_round_trips(|_| vec!["not-in-the-schema".into()]);
+}
@@ -0,0 +1,117 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+use std::{convert::TryFrom, str::FromStr};
+
+use serde_json::{json, Value};
+
+/// Generated from `tests/fixtures/top-level`, which has a message without `data` object
+#[allow(dead_code, unused_imports, clippy::all)]
+mod top_level {
+    include!(concat!(env!("OUT_DIR"), "/fixture_top_level.rs"));
+}
+
+/// Generated from `tests/fixtures/discriminator`, whose message type is called `kind`
+#[allow(dead_code, unused_imports, clippy::all)]
+mod discriminator {
+    include!(concat!(env!("OUT_DIR"), "/fixture_discriminator.rs"));
+}
+
+#[test]
+fn top_level_message_parses() {
+    use top_level::{Message, MessageBase};
+
+    let json = r#"{"messageType":2,"pluginId":"p","adapterId":"a","prompt":"Press the button"}"#;
+    let message = Message::from_str(json).unwrap();
+
+    assert_eq!(message.plugin_id(), "p");
+    match &message {
+        Message::AdapterPairingPromptNotification(msg) => {
+            assert_eq!(msg.data.adapter_id, "a");
+            assert_eq!(msg.data.prompt, "Press the button");
+            assert_eq!(msg.data.url, None);
+        }
+        _ => panic!("Parsed as {}", message.name()),
+    }
+    assert_eq!(
+        serde_json::to_value(&message).unwrap(),
+        serde_json::from_str::<Value>(json).unwrap()
+    );
+}
+
+#[test]
+fn header_of_a_top_level_message() {
+    use webthings_gateway_ipc_types::MessageHeader;
+
+    let json = r#"{"messageType":2,"pluginId":"p","adapterId":"a","prompt":"Press the button"}"#;
+    let header = MessageHeader::from_str(json).unwrap();
+
+    assert_eq!(
+        header,
+        MessageHeader {
+            message_type: 2,
+            plugin_id: Some("p".to_owned()),
+            correlation_id: None,
+        }
+    );
+}
+
+#[test]
+fn nested_message_parses_next_to_a_top_level_one() {
+    use top_level::{Message, MessageBase};
+
+    let message = Message::from_str(r#"{"messageType":1,"data":{"pluginId":"p"}}"#).unwrap();
+
+    assert_eq!(message.name(), "PluginUnloadRequest");
+    assert_eq!(message.plugin_id(), "p");
+    assert_eq!(
+        serde_json::to_value(&message).unwrap(),
+        json!({"messageType": 1, "data": {"pluginId": "p"}})
+    );
+}
+
+#[test]
+fn discriminator_is_read_from_the_schema() {
+    use discriminator::{Message, MESSAGE_TYPE_KEY};
+
+    let json = json!({"kind": 2, "data": {"pluginId": "p", "message": "boom"}});
+    let message = Message::from_str(&json.to_string()).unwrap();
+
+    assert_eq!(MESSAGE_TYPE_KEY, "kind");
+    assert_eq!(message.name(), "PluginErrorNotification");
+    assert_eq!(serde_json::to_value(&message).unwrap(), json);
+    assert!(Message::from_str(r#"{"messageType":2,"data":{"pluginId":"p"}}"#).is_err());
+}
+
+#[test]
+fn direction_is_read_from_the_schema() {
+    use discriminator::{IncomingToGateway, Message};
+
+    // Not listed in `MESSAGE_DIRECTIONS` and named like a message to the plugin
+    let json = json!({"kind": 3, "data": {"pluginId": "p"}});
+    let message = Message::from_str(&json.to_string()).unwrap();
+
+    assert!(IncomingToGateway::try_from(message).is_ok());
+}
+
+#[test]
+fn enum_names_do_not_clash() {
+    use discriminator::Message;
+
+    // `severityLevel` of the data and `level` of its `severity` would both be named
+    // `PluginErrorNotificationMessageDataSeverityLevel`, only the first gets the enum
+    let json = json!({
+        "kind": 2,
+        "data": {
+            "pluginId": "p",
+            "message": "boom",
+            "severity": {"level": "high"},
+            "severityLevel": "low"
+        }
+    });
+    let message = Message::from_str(&json.to_string()).unwrap();
+
+    assert_eq!(serde_json::to_value(&message).unwrap(), json);
+}
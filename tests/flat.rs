@@ -0,0 +1,55 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+#[allow(dead_code)]
+mod common;
+
+use std::str::FromStr;
+
+use serde_json::Value;
+use webthings_gateway_ipc_types::{
+    Message, MessageBase, MessageType, PluginUnloadRequest, MESSAGE_IDS, MESSAGE_TYPE_KEY,
+};
+
+#[test]
+fn nested_message_is_flattened() {
+    let json = format!(
+        r#"{{"{}":{},"pluginId":"p"}}"#,
+        MESSAGE_TYPE_KEY,
+        PluginUnloadRequest::MESSAGE_ID
+    );
+    let message = Message::from_flat_str(&json).unwrap();
+
+    assert_eq!(message.message_id(), PluginUnloadRequest::MESSAGE_ID);
+    assert_eq!(message.plugin_id(), "p");
+    assert_eq!(
+        serde_json::from_str::<Value>(&message.to_flat_string().unwrap()).unwrap(),
+        serde_json::from_str::<Value>(&json).unwrap()
+    );
+}
+
+#[test]
+fn every_message_round_trips_flat() {
+    for id in MESSAGE_IDS.iter() {
+        let nested = common::round_trip(&common::instance(*id));
+        let message = Message::from_str(&nested.to_string()).unwrap();
+
+        let flat: Value = serde_json::from_str(&message.to_flat_string().unwrap()).unwrap();
+        match nested.get("data") {
+            // Messages without `data` are flat already and kept as they are
+            None => assert_eq!(flat, nested),
+            Some(data) => {
+                assert_eq!(flat.get("data"), None);
+                for (key, value) in data.as_object().unwrap() {
+                    assert_eq!(flat.get(key), Some(value), "{}", key);
+                }
+            }
+        }
+
+        let back = Message::from_flat_str(&flat.to_string()).unwrap();
+        assert_eq!(back.message_id(), *id);
+        assert_eq!(serde_json::to_value(&back).unwrap(), nested);
+    }
+}
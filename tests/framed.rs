@@ -0,0 +1,123 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+use std::{env, fs, process, str::FromStr};
+
+use webthings_gateway_ipc_types::{
+    read_framed, read_framed_with_max, read_message, FramedReader, Message, MessageBase,
+    MessageReader, MessageType, PluginUnloadRequest, DEFAULT_MAX_FRAME_LEN,
+};
+
+fn framed_message() -> Vec<u8> {
+    let json = format!(
+        r#"{{"messageType":{},"data":{{"pluginId":"p"}}}}"#,
+        PluginUnloadRequest::MESSAGE_ID
+    );
+    Message::from_str(&json).unwrap().to_framed_bytes().unwrap()
+}
+
+#[test]
+fn framed_round_trip() {
+    let bytes = framed_message();
+    let mut reader = &bytes[..];
+
+    assert_eq!(read_framed(&mut reader).unwrap().unwrap().plugin_id(), "p");
+    assert!(read_framed(&mut reader).unwrap().is_none());
+}
+
+#[test]
+fn empty_stream_has_no_frame() {
+    assert!(read_framed(&mut &b""[..]).unwrap().is_none());
+}
+
+#[test]
+fn partial_length_prefix_fails() {
+    let bytes = framed_message();
+
+    for len in 1..4 {
+        assert!(read_framed(&mut &bytes[..len]).is_err());
+    }
+}
+
+#[test]
+fn partial_payload_fails() {
+    let bytes = framed_message();
+
+    assert!(read_framed(&mut &bytes[..bytes.len() - 1]).is_err());
+}
+
+#[test]
+fn oversize_frame_fails_before_its_payload() {
+    let bytes = framed_message();
+    let max_len = bytes.len() - 5;
+    let mut reader = &bytes[..];
+
+    let error = read_framed_with_max(&mut reader, max_len).unwrap_err();
+    assert!(error.to_string().contains("exceeds the maximum length"));
+    assert_eq!(reader.len(), bytes.len() - 4);
+}
+
+#[test]
+fn oversize_length_prefix_fails_without_a_payload() {
+    let prefix = (DEFAULT_MAX_FRAME_LEN as u32 + 1).to_be_bytes();
+
+    assert!(read_framed(&mut &prefix[..]).is_err());
+}
+
+#[test]
+fn oversize_line_fails() {
+    let json = format!(
+        "{{\"messageType\":{},\"data\":{{\"pluginId\":\"p\"}}}}\n",
+        PluginUnloadRequest::MESSAGE_ID
+    );
+    let mut reader = MessageReader::new(json.as_bytes()).with_max_len(json.len() - 1);
+
+    let error = reader.read().unwrap_err();
+    assert!(error.to_string().contains("exceeds the maximum length"));
+}
+
+#[test]
+fn read_message_limits_the_line() {
+    let line = vec![b' '; DEFAULT_MAX_FRAME_LEN + 1];
+
+    let error = read_message(&mut &line[..]).unwrap_err();
+    assert!(error.to_string().contains("exceeds the maximum length"));
+}
+
+#[test]
+fn read_lines_from_file_limits_the_line() {
+    let path = env::temp_dir().join(format!("oversize-capture-{}.jsonl", process::id()));
+    let json = format!(
+        "{{\"messageType\":{},\"data\":{{\"pluginId\":\"p\"}}}}\n",
+        PluginUnloadRequest::MESSAGE_ID
+    );
+    let mut capture = json.into_bytes();
+    capture.resize(capture.len() + DEFAULT_MAX_FRAME_LEN + 1, b' ');
+    fs::write(&path, capture).unwrap();
+
+    let result = Message::read_lines_from_file(&path);
+    fs::remove_file(&path).unwrap();
+
+    let error = result.unwrap_err().to_string();
+    assert!(error.contains("Line 2: Frame exceeds the maximum length"));
+}
+
+#[test]
+fn raw_frame_keeps_invalid_utf8() {
+    let json = format!(
+        "{{\"messageType\":{},\"data\":{{\"pluginId\":\"p\"}}}}\n",
+        PluginUnloadRequest::MESSAGE_ID
+    );
+    let mut input = b"\xff\xfe\n".to_vec();
+    input.extend_from_slice(json.as_bytes());
+    let mut reader = FramedReader::new(&input[..]);
+
+    let frame = reader.next_frame().unwrap().unwrap_err();
+    assert_eq!(frame.raw, b"\xff\xfe\n");
+    let (message, raw) = reader.next_frame().unwrap().unwrap();
+    assert_eq!(message.plugin_id(), "p");
+    assert_eq!(raw, json);
+    assert!(reader.next_frame().is_none());
+}
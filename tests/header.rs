@@ -0,0 +1,38 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+use std::str::FromStr;
+
+use webthings_gateway_ipc_types::{MessageHeader, MessageType, PluginUnloadRequest};
+
+#[test]
+fn header_of_a_nested_message() {
+    let json = format!(
+        r#"{{"data":{{"pluginId":"p","messageId":7,"device":{{"id":"d"}}}},"messageType":{}}}"#,
+        PluginUnloadRequest::MESSAGE_ID
+    );
+
+    assert_eq!(
+        MessageHeader::from_str(&json).unwrap(),
+        MessageHeader {
+            message_type: PluginUnloadRequest::MESSAGE_ID,
+            plugin_id: Some("p".to_owned()),
+            correlation_id: Some(7),
+        }
+    );
+}
+
+#[test]
+fn header_without_plugin_id() {
+    let header = MessageHeader::from_str(r#"{"messageType":-1,"data":{"level":1}}"#).unwrap();
+
+    assert_eq!(header.message_type, -1);
+    assert_eq!(header.plugin_id, None);
+}
+
+#[test]
+fn header_without_message_type_fails() {
+    assert!(MessageHeader::from_str(r#"{"data":{"pluginId":"p"}}"#).is_err());
+}
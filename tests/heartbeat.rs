@@ -0,0 +1,34 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+use std::str::FromStr;
+
+use webthings_gateway_ipc_types::{Message, MessageBase, PING_MESSAGE_TYPE, PONG_MESSAGE_TYPE};
+
+#[test]
+fn ping_and_pong_round_trip() {
+    let ping = serde_json::to_string(&Message::ping()).unwrap();
+    let pong = serde_json::to_string(&Message::pong()).unwrap();
+
+    let ping = Message::from_str_or_unknown(&ping).unwrap();
+    let pong = Message::from_str_or_unknown(&pong).unwrap();
+
+    assert!(ping.is_ping() && !ping.is_pong());
+    assert!(pong.is_pong() && !pong.is_ping());
+    assert_eq!(ping.message_id(), PING_MESSAGE_TYPE);
+    assert_eq!(pong.message_id(), PONG_MESSAGE_TYPE);
+}
+
+#[test]
+fn heartbeats_are_unknown_to_from_str() {
+    let ping = serde_json::to_string(&Message::ping()).unwrap();
+
+    assert_eq!(
+        Message::from_str(&ping)
+            .unwrap_err()
+            .unknown_message_type_id(),
+        Some(PING_MESSAGE_TYPE)
+    );
+}
@@ -0,0 +1,63 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use webthings_gateway_ipc_types::{Message, MessageType, PluginErrorNotification};
+
+struct Capture(Mutex<Vec<(Level, String)>>);
+
+impl Log for Capture {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        self.0
+            .lock()
+            .unwrap()
+            .push((record.level(), record.args().to_string()));
+    }
+
+    fn flush(&self) {}
+}
+
+static CAPTURE: Capture = Capture(Mutex::new(Vec::new()));
+
+#[test]
+fn parse_and_serialize_are_logged() {
+    log::set_logger(&CAPTURE).unwrap();
+    log::set_max_level(LevelFilter::Trace);
+
+    let json = format!(
+        r#"{{"messageType":{},"data":{{"pluginId":"p","message":"boom"}}}}"#,
+        PluginErrorNotification::MESSAGE_ID
+    );
+    let message = Message::from_str(&json).unwrap();
+    let serialized = serde_json::to_string(&message).unwrap();
+    assert!(Message::from_str("{").is_err());
+
+    let records = CAPTURE.0.lock().unwrap();
+    assert!(records.contains(&(
+        Level::Trace,
+        format!(
+            "Parsed PluginErrorNotification of plugin p from {} bytes",
+            json.len()
+        )
+    )));
+    assert!(records.contains(&(
+        Level::Trace,
+        format!(
+            "Serialized PluginErrorNotification to {} bytes of JSON",
+            serialized.len()
+        )
+    )));
+    assert!(records
+        .iter()
+        .any(|(level, message)| *level == Level::Debug
+            && message.starts_with("Could not parse message from 1 bytes")));
+}
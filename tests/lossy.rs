@@ -0,0 +1,24 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+use webthings_gateway_ipc_types::{DeviceAddedNotification, Message, MessageBase, MessageType};
+
+#[test]
+fn best_effort_guesses_a_missing_message_type() {
+    let message =
+        Message::from_str_best_effort(r#"{"data":{"pluginId":"p","message":"boom"}}"#).unwrap();
+
+    assert_eq!(message.plugin_id(), "p");
+}
+
+#[test]
+fn best_effort_keeps_a_known_message_type() {
+    let json = format!(
+        r#"{{"messageType":{},"data":{{"pluginId":"p"}}}}"#,
+        DeviceAddedNotification::MESSAGE_ID
+    );
+
+    assert!(Message::from_str_best_effort(&json).is_err());
+}
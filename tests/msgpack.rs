@@ -0,0 +1,53 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+#[allow(dead_code)]
+mod common;
+
+use serde_json::Value;
+use webthings_gateway_ipc_types::{
+    transcode_json_to_msgpack, transcode_msgpack_to_json, Message, MessageBase, MessageType,
+    PluginErrorNotification, MESSAGE_IDS,
+};
+
+fn plugin_error_json() -> String {
+    format!(
+        r#"{{"messageType":{},"data":{{"pluginId":"p","message":"boom"}}}}"#,
+        PluginErrorNotification::MESSAGE_ID
+    )
+}
+
+#[test]
+fn transcode_round_trip() {
+    let json = plugin_error_json();
+
+    let bytes = transcode_json_to_msgpack(&json).unwrap();
+    let back = transcode_msgpack_to_json(&bytes).unwrap();
+
+    assert_eq!(
+        serde_json::from_str::<Value>(&back).unwrap(),
+        serde_json::from_str::<Value>(&json).unwrap()
+    );
+    assert!(Message::from_msgpack(&bytes).is_ok());
+}
+
+#[test]
+fn transcode_rejects_an_unknown_message_type() {
+    let error = transcode_json_to_msgpack(r#"{"messageType":-1,"data":{}}"#).unwrap_err();
+
+    assert_eq!(error.unknown_message_type_id(), Some(-1));
+}
+
+#[test]
+fn every_message_round_trips() {
+    for id in MESSAGE_IDS.iter() {
+        let value = common::instance(*id);
+        let message = Message::from_msgpack(&rmp_serde::to_vec_named(&value).unwrap()).unwrap();
+
+        let back = Message::from_msgpack(&message.to_msgpack().unwrap()).unwrap();
+        assert_eq!(back.message_id(), *id);
+        assert_eq!(serde_json::to_value(&back).unwrap(), common::round_trip(&value));
+    }
+}
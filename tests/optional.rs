@@ -0,0 +1,22 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+#[allow(dead_code)]
+mod common;
+
+use common::Instance;
+use webthings_gateway_ipc_types::MESSAGE_IDS;
+
+#[test]
+fn unset_optional_fields_are_omitted() {
+    for id in MESSAGE_IDS.iter() {
+        let instance = Instance::new(*id, false);
+
+        let serialized = common::round_trip(&instance.value);
+        for pointer in &instance.omitted {
+            assert_eq!(serialized.pointer(pointer), None, "{}", pointer);
+        }
+    }
+}
@@ -0,0 +1,46 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+use std::str::FromStr;
+
+use serde::Serialize;
+use serde_json::{ser::PrettyFormatter, Serializer};
+use webthings_gateway_ipc_types::{Message, MessageType, PluginUnloadRequest};
+
+fn message() -> Message {
+    Message::from_str(&format!(
+        r#"{{"messageType":{},"data":{{"pluginId":"p"}}}}"#,
+        PluginUnloadRequest::MESSAGE_ID
+    ))
+    .unwrap()
+}
+
+#[test]
+fn pretty_serializer_indents() {
+    assert_eq!(
+        serde_json::to_string_pretty(&message()).unwrap(),
+        format!(
+            "{{\n  \"data\": {{\n    \"pluginId\": \"p\"\n  }},\n  \"messageType\": {}\n}}",
+            PluginUnloadRequest::MESSAGE_ID
+        )
+    );
+}
+
+#[test]
+fn custom_formatter_is_used() {
+    let mut json = Vec::new();
+    let formatter = PrettyFormatter::with_indent(b"\t");
+    message()
+        .serialize(&mut Serializer::with_formatter(&mut json, formatter))
+        .unwrap();
+
+    assert_eq!(
+        String::from_utf8(json).unwrap(),
+        format!(
+            "{{\n\t\"data\": {{\n\t\t\"pluginId\": \"p\"\n\t}},\n\t\"messageType\": {}\n}}",
+            PluginUnloadRequest::MESSAGE_ID
+        )
+    );
+}
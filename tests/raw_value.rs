@@ -0,0 +1,48 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+#[allow(dead_code)]
+mod common;
+
+use std::str::FromStr;
+
+use webthings_gateway_ipc_types::{Message, PluginUnloadRequest, MESSAGE_IDS};
+
+const RAW: &str = r#"{"b": 1,  "a": [1.50, "x"]}"#;
+
+#[test]
+fn property_values_keep_their_bytes() {
+    let mut checked = 0;
+
+    for id in MESSAGE_IDS.iter() {
+        let mut value = common::instance(*id);
+        let pointer = match ["/data/propertyValue", "/data/property/value"]
+            .iter()
+            .find(|pointer| value.pointer(pointer).is_some())
+        {
+            Some(pointer) => pointer,
+            None => continue,
+        };
+        *value.pointer_mut(pointer).unwrap() = serde_json::json!("placeholder");
+
+        let json = value
+            .to_string()
+            .replacen("\"placeholder\"", RAW, 1);
+        let message = Message::from_str(&json).unwrap();
+        if let Some((_, raw)) = message.property() {
+            assert_eq!(raw.get(), RAW);
+            checked += 1;
+        }
+    }
+
+    assert!(checked > 0, "No message has a property value");
+}
+
+#[test]
+fn structs_without_raw_values_keep_partial_eq() {
+    fn assert_partial_eq<T: PartialEq>() {}
+
+    assert_partial_eq::<PluginUnloadRequest>();
+}
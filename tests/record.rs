@@ -0,0 +1,40 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+use std::str::FromStr;
+
+use webthings_gateway_ipc_types::{
+    Direction, Message, MessageLogRecord, MessageType, PluginErrorNotification,
+    PluginUnloadRequest,
+};
+
+#[test]
+fn record_has_the_direction_of_the_message() {
+    let notification = Message::from_str(&format!(
+        r#"{{"messageType":{},"data":{{"pluginId":"p","message":"x"}}}}"#,
+        PluginErrorNotification::MESSAGE_ID
+    ))
+    .unwrap();
+    let request = Message::from_str(&format!(
+        r#"{{"messageType":{},"data":{{"pluginId":"p"}}}}"#,
+        PluginUnloadRequest::MESSAGE_ID
+    ))
+    .unwrap();
+
+    let record = MessageLogRecord::from(&notification);
+    assert_eq!(record.direction, Some(Direction::ToGateway));
+    assert_eq!(record.name, "PluginErrorNotification");
+    assert_eq!(
+        MessageLogRecord::from(&request).direction,
+        Some(Direction::ToPlugin)
+    );
+}
+
+#[test]
+fn unknown_messages_have_no_direction() {
+    let message = Message::from_str_or_unknown(r#"{"messageType":-1,"data":{}}"#).unwrap();
+
+    assert_eq!(MessageLogRecord::from(&message).direction, None);
+}
@@ -0,0 +1,75 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+use serde_json::Value;
+use webthings_gateway_ipc_types::{
+    Error, MessageBase, MessageRegistry, MessageType, PluginUnloadRequest, RegistryMessage,
+};
+
+const CUSTOM_ID: i64 = -4200;
+
+fn parse_custom(s: &str) -> Result<String, Error> {
+    let value: Value = serde_json::from_str(s).map_err(Error::custom)?;
+    value
+        .pointer("/data/text")
+        .and_then(Value::as_str)
+        .map(str::to_owned)
+        .ok_or_else(|| Error::custom("Custom message without text"))
+}
+
+fn serialize_custom(text: &String) -> Result<String, Error> {
+    Ok(format!(
+        r#"{{"messageType":{},"data":{{"text":{:?}}}}}"#,
+        CUSTOM_ID, text
+    ))
+}
+
+fn registry() -> MessageRegistry<String> {
+    let mut registry = MessageRegistry::new();
+    registry
+        .register(CUSTOM_ID, parse_custom, serialize_custom)
+        .unwrap();
+    registry
+}
+
+#[test]
+fn custom_message_round_trip() {
+    let registry = registry();
+    let json = serialize_custom(&"hello".to_owned()).unwrap();
+
+    let message = registry.from_str(&json).unwrap();
+    assert!(matches!(&message, RegistryMessage::Custom { message, .. } if message == "hello"));
+    assert_eq!(registry.to_string(&message).unwrap(), json);
+}
+
+#[test]
+fn custom_parse_error_is_reported() {
+    let json = format!(r#"{{"messageType":{},"data":{{}}}}"#, CUSTOM_ID);
+
+    let error = registry().from_str(&json).unwrap_err();
+    assert!(error.to_string().ends_with("Custom message without text"));
+}
+
+#[test]
+fn builtin_types_are_dispatched_first() {
+    let json = format!(
+        r#"{{"messageType":{},"data":{{"pluginId":"p"}}}}"#,
+        PluginUnloadRequest::MESSAGE_ID
+    );
+
+    match registry().from_str(&json).unwrap() {
+        RegistryMessage::Builtin(message) => assert_eq!(message.plugin_id(), "p"),
+        RegistryMessage::Custom { .. } => panic!("Dispatched a built-in type to the registry"),
+    }
+}
+
+#[test]
+fn builtin_types_cannot_be_registered() {
+    let mut registry = registry();
+
+    assert!(registry
+        .register(PluginUnloadRequest::MESSAGE_ID, parse_custom, serialize_custom)
+        .is_err());
+}
@@ -0,0 +1,36 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+#[allow(dead_code)]
+mod common;
+
+use webthings_gateway_ipc_types::*;
+
+/// A round trip test of a message type built from its schema,
+/// and a check that its data struct converts into a message of that type
+macro_rules! message_test {
+    ($test:ident, $name:ident, $data:ident) => {
+        #[test]
+        fn $test() {
+            let id = <$name as MessageType>::MESSAGE_ID;
+            common::assert_round_trip(id);
+
+            let mut value = common::instance(id);
+            let data = match value.get("data") {
+                Some(data) => data.clone(),
+                // Messages defined at the top level of their schema have no data object
+                None => {
+                    value.as_object_mut().unwrap().remove(MESSAGE_TYPE_KEY);
+                    value
+                }
+            };
+            let data: $data = serde_json::from_value(data).unwrap();
+            let message: Message = data.into();
+            assert_eq!(message.message_id(), id);
+        }
+    };
+}
+
+include!(concat!(env!("OUT_DIR"), "/message_list.rs"));
@@ -0,0 +1,28 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+use webthings_gateway_ipc_types::{Message, PluginErrorNotification, MESSAGE_IDS};
+
+#[test]
+fn every_message_type_has_a_derived_schema() {
+    let schemas = Message::derived_schemas();
+
+    assert_eq!(schemas.len(), MESSAGE_IDS.len());
+    for (name, schema) in &schemas {
+        assert_eq!(
+            schema.get("title").and_then(|title| title.as_str()),
+            Some(*name)
+        );
+    }
+}
+
+#[test]
+fn derived_schema_follows_the_serde_renames() {
+    let schema = schemars::schema_for!(PluginErrorNotification);
+    let value = serde_json::to_value(&schema).unwrap();
+
+    assert!(value.pointer("/properties/messageType").is_some());
+    assert!(value.pointer("/properties/data").is_some());
+}
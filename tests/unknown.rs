@@ -0,0 +1,75 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+use serde_json::json;
+use webthings_gateway_ipc_types::{Message, MessageBase, MissingPluginId};
+
+fn unknown(data: &str) -> Message {
+    Message::from_str_or_unknown(&format!(r#"{{"messageType":-1,"data":{}}}"#, data)).unwrap()
+}
+
+#[test]
+fn unknown_message_without_plugin_id() {
+    let message = unknown("{}");
+
+    assert_eq!(message.plugin_id_opt(), None);
+    assert!(message
+        .ensure_plugin_id("p", MissingPluginId::Accept)
+        .is_ok());
+    assert!(message
+        .ensure_plugin_id("p", MissingPluginId::Reject)
+        .is_err());
+    assert!(message.is_for_plugin("p", MissingPluginId::Accept));
+    assert!(!message.is_for_plugin("p", MissingPluginId::Reject));
+    assert_eq!(message.routing_key(), (None, -1));
+    assert_eq!(message.into_parts().unwrap().1, None);
+}
+
+#[test]
+fn unknown_message_with_plugin_id() {
+    let message = unknown(r#"{"pluginId":"p"}"#);
+
+    assert_eq!(message.plugin_id_opt(), Some("p"));
+    for missing in &[MissingPluginId::Accept, MissingPluginId::Reject] {
+        assert!(message.ensure_plugin_id("p", *missing).is_ok());
+        assert!(message.ensure_plugin_id("q", *missing).is_err());
+        assert!(message.is_for_plugin("p", *missing));
+        assert!(!message.is_for_plugin("q", *missing));
+    }
+    assert_eq!(message.routing_key(), (Some("p"), -1));
+}
+
+#[test]
+fn unknown_message_is_truncated() {
+    let message = unknown(r#"{"pluginId":"p","description":"abcdef"}"#);
+
+    let truncated = message.truncated(3).unwrap();
+
+    assert_eq!(truncated.message_id(), -1);
+    assert_eq!(
+        truncated.get("/data/description"),
+        Some("abc… (6 chars)".into())
+    );
+}
+
+#[test]
+fn unknown_messages_merge() {
+    let mut message = unknown(r#"{"pluginId":"p","level":1}"#);
+
+    message
+        .merge_from(&unknown(r#"{"level":2,"unit":null}"#))
+        .unwrap();
+
+    assert_eq!(message.message_id(), -1);
+    assert_eq!(
+        message.get("/data"),
+        Some(json!({"pluginId": "p", "level": 2}))
+    );
+}
+
+#[test]
+fn unknown_message_is_not_asserted_valid() {
+    unknown(r#"{"pluginId":"p"}"#).debug_assert_valid();
+}
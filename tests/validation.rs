@@ -0,0 +1,66 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+use std::str::FromStr;
+
+use serde_json::json;
+use webthings_gateway_ipc_types::{
+    Message, MessageType, PluginUnloadRequest, Validate, MESSAGE_IDS, MESSAGE_TYPE_KEY,
+};
+
+#[allow(dead_code)]
+mod common;
+
+#[test]
+fn valid_message() {
+    let json = format!(
+        r#"{{"messageType":{},"data":{{"pluginId":"p"}}}}"#,
+        PluginUnloadRequest::MESSAGE_ID
+    );
+    let message = Message::from_str(&json).unwrap();
+
+    assert_eq!(message.validate(), Ok(()));
+    match message {
+        Message::PluginUnloadRequest(msg) => assert_eq!(Validate::validate(&msg), Ok(())),
+        _ => panic!("Expected a PluginUnloadRequest"),
+    }
+}
+
+#[test]
+fn invalid_message() {
+    let mut value = json!({"data": {"pluginId": 1}});
+    value[MESSAGE_TYPE_KEY] = PluginUnloadRequest::MESSAGE_ID.into();
+
+    let errors = Message::validate_value(&value).unwrap_err();
+
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].instance_path, "/data/pluginId");
+    assert!(errors[0].schema_path.ends_with("/type"));
+}
+
+#[test]
+fn unknown_message_has_no_schema() {
+    let mut value = json!({"data": {}});
+    value[MESSAGE_TYPE_KEY] = (-1).into();
+
+    assert!(Message::validate_value(&value).is_err());
+}
+
+#[test]
+fn every_instance_is_valid() {
+    for id in MESSAGE_IDS.iter() {
+        let message = Message::from_str(&common::instance(*id).to_string()).unwrap();
+        assert_eq!(message.validate(), Ok(()), "{}", message.name());
+    }
+}
+
+#[test]
+fn every_instance_is_asserted_valid() {
+    for id in MESSAGE_IDS.iter() {
+        Message::from_str(&common::instance(*id).to_string())
+            .unwrap()
+            .debug_assert_valid();
+    }
+}
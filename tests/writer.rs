@@ -0,0 +1,103 @@
+/**
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+use std::{collections::BTreeMap, io::Write, str::FromStr};
+
+use serde_json::{json, Value};
+use webthings_gateway_ipc_types::{
+    json_len, write_message, DevicePropertyChangedNotification, Message, MessageType, MESSAGE_IDS,
+};
+
+#[allow(dead_code)]
+mod common;
+
+/// Keeps every write as a separate chunk
+#[derive(Default)]
+struct ChunkRecorder {
+    chunks: Vec<Vec<u8>>,
+}
+
+impl ChunkRecorder {
+    fn bytes(&self) -> Vec<u8> {
+        self.chunks.concat()
+    }
+
+    fn largest_chunk(&self) -> usize {
+        self.chunks.iter().map(Vec::len).max().unwrap_or(0)
+    }
+}
+
+impl Write for ChunkRecorder {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.chunks.push(buf.to_vec());
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A property change carrying an array of 10000 objects as its value
+fn large_property_message() -> Message {
+    let mut value = common::instance(DevicePropertyChangedNotification::MESSAGE_ID);
+    let items: Vec<Value> = (0..10_000)
+        .map(|i| json!({ "index": i, "label": format!("item {}", i), "on": i % 2 == 0 }))
+        .collect();
+    value["data"]["property"]["value"] = Value::Array(items);
+    Message::from_str(&value.to_string()).unwrap()
+}
+
+#[test]
+fn to_writer_streams_the_message() {
+    let message = large_property_message();
+    let mut writer = ChunkRecorder::default();
+
+    message.to_writer(&mut writer).unwrap();
+
+    let json = serde_json::to_string(&message).unwrap();
+    assert_eq!(writer.bytes(), json.as_bytes());
+    assert!(json.len() > 100_000);
+    // A raw property value is written as a single chunk
+    if cfg!(not(feature = "raw-value")) {
+        assert!(writer.largest_chunk() < 1024);
+    }
+}
+
+#[test]
+fn to_writer_pretty_streams_the_message() {
+    let message = large_property_message();
+    let mut writer = ChunkRecorder::default();
+
+    message.to_writer_pretty(&mut writer).unwrap();
+
+    assert_eq!(writer.bytes(), serde_json::to_vec_pretty(&message).unwrap());
+    // A raw property value is written as a single chunk
+    if cfg!(not(feature = "raw-value")) {
+        assert!(writer.largest_chunk() < 1024);
+    }
+}
+
+#[test]
+fn wire_len_is_the_length_of_the_frame() {
+    for id in MESSAGE_IDS.iter() {
+        let message = Message::from_str(&common::instance(*id).to_string()).unwrap();
+        let mut frame = Vec::new();
+        write_message(&mut frame, &message).unwrap();
+
+        assert_eq!(message.wire_len().unwrap(), frame.len());
+    }
+}
+
+#[test]
+fn json_len_fails_like_serialization() {
+    // JSON object keys have to be strings or numbers
+    let mut map = BTreeMap::new();
+    map.insert(vec![1], 1);
+
+    assert!(serde_json::to_vec(&map).is_err());
+    assert!(json_len(&map).is_err());
+    assert_eq!(json_len(&json!({"a": [1, 2]})).unwrap(), r#"{"a":[1,2]}"#.len());
+}